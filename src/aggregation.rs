@@ -0,0 +1,316 @@
+//! # Aggregation Module
+//!
+//! Groups deduplicated entries by any combination of dimensions, producing nested
+//! groups that the daily, monthly, version and `report` views are built from
+//!
+//! ## Key Components
+//! - [`group_entries`] - Build nested groups for a list of dimensions
+//! - [`Group`] - One group with its totals and sub-groups
+//! - [`UsageTotals`] - Token, cost and activity totals of a set of entries
+//! - [`sort_groups`] - Order top-level groups for display
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::cli::{GroupBy, SortOrder};
+use crate::data_sources::decode_project_dir_name;
+use crate::entry_processor::ProcessedEntry;
+use crate::pricing::PricingSource;
+use crate::table_display::{SidechainSplit, simplify_model_name};
+
+/// Totals over a set of entries
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageTotals {
+    pub entry_count: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    /// Part of `cache_creation_tokens` written with a 5-minute cache lifetime
+    pub cache_creation_5m_tokens: u64,
+    /// Part of `cache_creation_tokens` written with a 1-hour cache lifetime
+    pub cache_creation_1h_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    /// Simplified model names, in order of first use
+    pub models: Vec<String>,
+    pub session_count: usize,
+    pub sidechain: SidechainSplit,
+    /// `fallback` or `zero` if any entry's model had no known rates
+    pub pricing_source: PricingSource,
+    /// Requests billed at long-context (premium tier) rates
+    pub long_context_requests: usize,
+    /// Cost of those requests
+    pub long_context_cost_usd: f64,
+    pub first_seen: Option<DateTime<Local>>,
+    pub last_seen: Option<DateTime<Local>>,
+    #[serde(skip)]
+    sessions: HashSet<String>,
+}
+
+impl UsageTotals {
+    pub fn add(&mut self, entry: &ProcessedEntry) {
+        let tokens = entry.usage.input_tokens
+            + entry.usage.output_tokens
+            + entry.usage.cache_creation_input_tokens
+            + entry.usage.cache_read_input_tokens;
+
+        self.entry_count += 1;
+        self.input_tokens += entry.usage.input_tokens;
+        self.output_tokens += entry.usage.output_tokens;
+        self.cache_creation_tokens += entry.usage.cache_creation_input_tokens;
+        self.cache_creation_5m_tokens += entry.usage.cache_creation_5m_tokens();
+        self.cache_creation_1h_tokens += entry.usage.cache_creation_1h_tokens();
+        self.cache_read_tokens += entry.usage.cache_read_input_tokens;
+        self.total_tokens += tokens;
+        self.cost_usd += entry.cost;
+        self.sidechain.add(entry.is_sidechain, tokens, entry.cost);
+        self.pricing_source = self.pricing_source.combine(entry.pricing_source);
+        if entry.long_context {
+            self.long_context_requests += 1;
+            self.long_context_cost_usd += entry.cost;
+        }
+
+        let model = simplify_model_name(&entry.model);
+        if !self.models.contains(&model) {
+            self.models.push(model);
+        }
+        if self.sessions.insert(entry.session_id.clone()) {
+            self.session_count += 1;
+        }
+
+        self.first_seen = Some(self.first_seen.map_or(entry.timestamp, |first| first.min(entry.timestamp)));
+        self.last_seen = Some(self.last_seen.map_or(entry.timestamp, |last| last.max(entry.timestamp)));
+    }
+}
+
+/// Entries sharing one value of a dimension, optionally split further
+#[derive(Debug, Clone, Serialize)]
+pub struct Group {
+    pub dimension: GroupBy,
+    pub key: String,
+    /// Grouping value before conversion for display, e.g. the encoded project directory name
+    #[serde(skip)]
+    pub raw_key: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group>,
+}
+
+/// Totals over all entries
+pub fn total_usage(entries: &[ProcessedEntry]) -> UsageTotals {
+    let mut totals = UsageTotals::default();
+    for entry in entries {
+        totals.add(entry);
+    }
+    totals
+}
+
+/// Group entries by the first dimension, then each group by the next, and so on
+///
+/// Time and version groups are ordered oldest first; groups of other dimensions
+/// are ordered by cost, highest first.
+pub fn group_entries(entries: &[ProcessedEntry], dimensions: &[GroupBy]) -> Vec<Group> {
+    let refs: Vec<&ProcessedEntry> = entries.iter().collect();
+    group_level(&refs, dimensions)
+}
+
+fn group_level(entries: &[&ProcessedEntry], dimensions: &[GroupBy]) -> Vec<Group> {
+    let Some((&dimension, rest)) = dimensions.split_first() else {
+        return Vec::new();
+    };
+
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut buckets: Vec<(String, Vec<&ProcessedEntry>)> = Vec::new();
+    for &entry in entries {
+        let key = group_key(dimension, entry);
+        match positions.get(&key) {
+            Some(&position) => buckets[position].1.push(entry),
+            None => {
+                positions.insert(key.clone(), buckets.len());
+                buckets.push((key, vec![entry]));
+            }
+        }
+    }
+
+    let mut groups: Vec<Group> = buckets
+        .into_iter()
+        .map(|(key, members)| {
+            let mut totals = UsageTotals::default();
+            for entry in &members {
+                totals.add(entry);
+            }
+            Group {
+                dimension,
+                raw_key: key.clone(),
+                key: display_key(dimension, key),
+                totals,
+                groups: group_level(&members, rest),
+            }
+        })
+        .collect();
+
+    groups.sort_by(|a, b| default_order(dimension, a, b));
+    groups
+}
+
+/// Order top-level groups: by key for time and version dimensions, by cost otherwise
+pub fn sort_groups(groups: &mut [Group], order: SortOrder) {
+    groups.sort_by(|a, b| {
+        let ordering = if is_ordered_by_key(a.dimension) {
+            compare_keys(a.dimension, &a.key, &b.key)
+        } else {
+            a.totals.cost_usd.partial_cmp(&b.totals.cost_usd).unwrap_or(Ordering::Equal)
+        };
+        match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    });
+}
+
+fn group_key(dimension: GroupBy, entry: &ProcessedEntry) -> String {
+    match dimension {
+        GroupBy::Day => entry.date.clone(),
+        GroupBy::Week => entry.timestamp.format("%G-W%V").to_string(),
+        GroupBy::Month => entry.timestamp.format("%Y-%m").to_string(),
+        GroupBy::Hour => entry.timestamp.format("%Y-%m-%d %H:00").to_string(),
+        GroupBy::Model => entry.model.clone(),
+        GroupBy::Project => entry.project.clone(),
+        GroupBy::Cwd => or_unknown(&entry.cwd),
+        GroupBy::Session => or_unknown(&entry.session_id),
+        GroupBy::Version => or_unknown(&entry.version),
+        GroupBy::Sidechain => if entry.is_sidechain { "sidechain" } else { "main" }.to_string(),
+        // Requests without a recorded tier are billed at standard prices
+        GroupBy::Tier => entry.usage.service_tier.clone().unwrap_or_else(|| "standard".to_string()),
+    }
+}
+
+/// Turn a grouping key into its display form, once per group rather than per entry
+fn display_key(dimension: GroupBy, key: String) -> String {
+    match dimension {
+        GroupBy::Project => decode_project_dir_name(&key).display().to_string(),
+        _ => key,
+    }
+}
+
+fn or_unknown(value: &str) -> String {
+    if value.is_empty() { "unknown".to_string() } else { value.to_string() }
+}
+
+fn is_ordered_by_key(dimension: GroupBy) -> bool {
+    matches!(
+        dimension,
+        GroupBy::Day | GroupBy::Week | GroupBy::Month | GroupBy::Hour | GroupBy::Version
+    )
+}
+
+fn default_order(dimension: GroupBy, a: &Group, b: &Group) -> Ordering {
+    if is_ordered_by_key(dimension) {
+        compare_keys(dimension, &a.key, &b.key)
+    } else {
+        b.totals
+            .cost_usd
+            .partial_cmp(&a.totals.cost_usd)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.key.cmp(&b.key))
+    }
+}
+
+fn compare_keys(dimension: GroupBy, a: &str, b: &str) -> Ordering {
+    match dimension {
+        GroupBy::Version => compare_versions(a, b),
+        _ => a.cmp(b),
+    }
+}
+
+/// Compare dotted version strings numerically, so 1.0.10 sorts after 1.0.9
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<u64> {
+        version.split(['.', '-']).map(|part| part.parse().unwrap_or(u64::MAX)).collect()
+    };
+    parts(a).cmp(&parts(b)).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonl_parser::Usage;
+    use chrono::TimeZone;
+
+    fn entry(day: u32, model: &str, sidechain: bool, cost: f64) -> ProcessedEntry {
+        let timestamp = Local.with_ymd_and_hms(2025, 6, day, 12, 0, 0).unwrap();
+        ProcessedEntry {
+            date: timestamp.format("%Y-%m-%d").to_string(),
+            timestamp,
+            model: model.to_string(),
+            usage: Usage {
+                input_tokens: 100,
+                ..Default::default()
+            },
+            cost,
+            project: "-home-me-alpha".to_string(),
+            cwd: String::new(),
+            session_id: format!("s{}", day),
+            is_sidechain: sidechain,
+            version: "1.0.30".to_string(),
+            pricing_source: PricingSource::Known,
+            long_context: false,
+        }
+    }
+
+    #[test]
+    fn test_cache_writes_are_split_by_lifetime() {
+        let mut split = entry(1, "claude-sonnet-4-20250514", false, 1.0);
+        split.usage.cache_creation_input_tokens = 1_000;
+        split.usage.cache_creation = Some(crate::jsonl_parser::CacheCreation {
+            ephemeral_5m_input_tokens: 400,
+            ephemeral_1h_input_tokens: 600,
+        });
+        // Logs without a split only have 5-minute writes
+        let mut unsplit = entry(2, "claude-sonnet-4-20250514", false, 1.0);
+        unsplit.usage.cache_creation_input_tokens = 50;
+
+        let groups = group_entries(&[split, unsplit], &[GroupBy::Month]);
+        let totals = &groups[0].totals;
+        assert_eq!((totals.cache_creation_5m_tokens, totals.cache_creation_1h_tokens), (450, 600));
+
+        let json = serde_json::to_value(&groups[0]).unwrap();
+        assert_eq!(json["cache_creation_5m_tokens"], 450);
+        assert_eq!(json["cache_creation_1h_tokens"], 600);
+    }
+
+    #[test]
+    fn test_nested_groups_add_up_at_every_level() {
+        let entries = vec![
+            entry(2, "claude-opus-4-20250514", false, 5.0),
+            entry(1, "claude-sonnet-4-20250514", false, 1.0),
+            entry(2, "claude-sonnet-4-20250514", true, 2.0),
+            entry(9, "claude-sonnet-4-20250514", false, 4.0),
+        ];
+
+        let groups = group_entries(&entries, &[GroupBy::Week, GroupBy::Model, GroupBy::Sidechain]);
+
+        // 2025-06-01 is a Sunday, so it closes ISO week 22
+        assert_eq!(groups.iter().map(|g| g.key.as_str()).collect::<Vec<_>>(), vec!["2025-W22", "2025-W23", "2025-W24"]);
+        let week = &groups[1];
+        assert_eq!(week.totals.cost_usd, 7.0);
+        assert_eq!(week.totals.session_count, 1);
+        // Models by cost, highest first
+        assert_eq!(week.groups[0].key, "claude-opus-4-20250514");
+        let sonnet = &week.groups[1];
+        assert_eq!(sonnet.groups.len(), 1);
+        assert_eq!(sonnet.groups[0].key, "sidechain");
+        assert!(sonnet.groups[0].groups.is_empty());
+
+        let total: f64 = groups.iter().map(|g| g.totals.cost_usd).sum();
+        assert_eq!(total, total_usage(&entries).cost_usd);
+
+        let mut by_cost = group_entries(&entries, &[GroupBy::Model]);
+        sort_groups(&mut by_cost, SortOrder::Asc);
+        assert_eq!(by_cost[0].key, "claude-opus-4-20250514");
+    }
+}
@@ -78,6 +78,7 @@ impl Block {
         }
     }
 
+    #[allow(clippy::collapsible_if)]
     fn add_session(&mut self, session: &SessionData) {
         self.entries += 1;
         
@@ -128,6 +129,7 @@ impl Block {
         }
     }
 
+    #[allow(clippy::collapsible_if)]
     fn calculate_burn_rate(&mut self) {
         if let (Some(actual_end), start_time) = (&self.actual_end_time, &self.start_time) {
             if let (Ok(start), Ok(end)) = (
@@ -148,6 +150,7 @@ impl Block {
         }
     }
 
+    #[allow(clippy::unnecessary_unwrap)]
     fn finalize(&mut self, end_time: DateTime<Utc>) {
         if self.actual_end_time.is_none() {
             self.end_time = end_time.to_rfc3339();
@@ -228,6 +231,7 @@ impl BlockBuilder {
         self.blocks
     }
 
+    #[allow(clippy::collapsible_if)]
     fn mark_active_block(&mut self, current_time: DateTime<Utc>) {
         // Find the most recent non-gap block
         let mut most_recent_block_idx = None;
@@ -254,6 +258,7 @@ impl BlockBuilder {
         }
     }
 
+    #[allow(clippy::collapsible_if)]
     fn insert_gap_blocks(&mut self) {
         let original_blocks = std::mem::take(&mut self.blocks);
         let mut blocks_with_gaps = Vec::new();
@@ -285,11 +290,13 @@ impl BlockBuilder {
     }
 }
 
-pub fn build_blocks_from_sessions(sessions: &[SessionData]) -> Result<Vec<Block>> {
+pub fn build_blocks_from_sessions<'a>(
+    sessions: impl IntoIterator<Item = &'a SessionData>,
+) -> Result<Vec<Block>> {
     let mut builder = BlockBuilder::new();
 
     // Sort sessions by start time
-    let mut session_refs: Vec<_> = sessions.iter().collect();
+    let mut session_refs: Vec<_> = sessions.into_iter().collect();
     session_refs.sort_by_key(|s| s.start_time);
 
     // Process each session
@@ -306,6 +313,7 @@ pub fn build_blocks_from_sessions(sessions: &[SessionData]) -> Result<Vec<Block>
 mod tests {
     use super::*;
     use crate::jsonl_parser::{SessionData, ModelUsage};
+    use crate::pricing::PricingSource;
     use std::collections::HashMap;
 
     fn create_test_session(
@@ -323,9 +331,13 @@ mod tests {
             total_input: tokens / 2,
             total_output: tokens / 2,
             total_cache_write: 0,
+            total_cache_write_5m: 0,
+            total_cache_write_1h: 0,
             total_cache_read: 0,
             message_count: 1,
             weighted_tokens: tokens,
+            cost_usd: 0.0,
+            pricing_source: PricingSource::Known,
         });
 
         SessionData {
@@ -334,8 +346,8 @@ mod tests {
             end_time: Some(end_time),
             model_usage,
             total_weighted_tokens: tokens,
-            has_limit_error: false,
-            _limit_type: None,
+            sidechain: Default::default(),
+            long_context_cost_usd: 0.0,
         }
     }
 
@@ -0,0 +1,320 @@
+//! # Cache Module
+//!
+//! Persistent on-disk cache of parsed usage records, keyed by file fingerprint
+//!
+//! ## Key Components
+//! - [`EntryCache`] - Per-file usage records reused across runs, one cache file per session file
+//! - [`FileFingerprint`] - Size and modification time identifying a file's content
+//! - [`clear_cache`] - Remove all cached data
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::entry_processor::FileRecords;
+
+/// Version of the parsed record format; bump whenever parsing or [`FileRecords`] changes
+pub const PARSER_VERSION: u32 = 10;
+
+/// Directory under [`cache_dir`] holding one cache file per session file
+const ENTRIES_DIR_NAME: &str = "entries";
+
+/// Maps cache file names back to session files, so entries of deleted files can be removed
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// Size and modification time of a file at the time it was parsed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    pub size: u64,
+    pub mtime_ns: u64,
+}
+
+impl FileFingerprint {
+    pub fn of(path: &Path) -> Result<Self> {
+        let metadata = std::fs::metadata(path).context("Failed to stat session file")?;
+        let mtime_ns = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+
+        Ok(Self {
+            size: metadata.len(),
+            mtime_ns,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedFile {
+    parser_version: u32,
+    path: PathBuf,
+    fingerprint: FileFingerprint,
+    parsed: FileRecords,
+}
+
+/// Where a file's records came from in [`EntryCache::records_for_all`]
+enum Lookup {
+    /// Already in memory and unchanged
+    Fresh,
+    /// Read from the file's cache file
+    Loaded(CachedFile),
+    /// Parsed from the session file itself
+    Parsed(CachedFile),
+}
+
+/// Parsed usage records per file, persisted under the user cache directory
+///
+/// Each session file has its own cache file, so a run only reads the cache of
+/// the files it reports on and only rewrites the cache of files that changed.
+#[derive(Debug)]
+pub struct EntryCache {
+    dir: Option<PathBuf>,
+    files: HashMap<PathBuf, CachedFile>,
+    /// Files parsed this run whose cache file still has to be written
+    dirty: HashSet<PathBuf>,
+    /// Files that are never read from or written to disk
+    transient: HashSet<PathBuf>,
+}
+
+impl EntryCache {
+    /// Use the cache under the user cache directory
+    ///
+    /// Nothing is read up front; cache files are loaded on demand by
+    /// [`EntryCache::records_for_all`], and missing, unreadable or outdated ones
+    /// are ignored.
+    pub fn load() -> Self {
+        Self::in_dir(cache_dir().map(|dir| dir.join(ENTRIES_DIR_NAME)))
+    }
+
+    /// A cache that never reads from or writes to disk (`--no-cache`)
+    pub fn disabled() -> Self {
+        Self::in_dir(None)
+    }
+
+    fn in_dir(dir: Option<PathBuf>) -> Self {
+        Self {
+            dir,
+            files: HashMap::new(),
+            dirty: HashSet::new(),
+            transient: HashSet::new(),
+        }
+    }
+
+    /// Never cache `path` on disk, e.g. the temporary copy of stdin
+    pub fn exclude(&mut self, path: &Path) {
+        self.transient.insert(path.to_path_buf());
+    }
+
+    /// Parsed records for every file, in input order
+    ///
+    /// Files that changed since they were cached (or were never cached) are
+    /// parsed with `parse`; everything else comes from the cache. Both happen
+    /// in parallel.
+    pub fn records_for_all<F>(&mut self, paths: &[PathBuf], parse: F) -> Vec<Result<&FileRecords>>
+    where
+        F: Fn(&Path) -> Result<FileRecords> + Sync,
+    {
+        let lookups: Vec<Result<Lookup>> = paths
+            .par_iter()
+            .map(|path| {
+                let fingerprint = FileFingerprint::of(path)?;
+                if self.files.get(path).is_some_and(|cached| cached.fingerprint == fingerprint) {
+                    return Ok(Lookup::Fresh);
+                }
+
+                if !self.transient.contains(path)
+                    && let Some(cached) = self.read_cached(path)
+                    && cached.fingerprint == fingerprint
+                {
+                    return Ok(Lookup::Loaded(cached));
+                }
+
+                let parsed = parse(path)?;
+                Ok(Lookup::Parsed(CachedFile {
+                    parser_version: PARSER_VERSION,
+                    path: path.clone(),
+                    fingerprint,
+                    parsed,
+                }))
+            })
+            .collect();
+
+        let mut results: Vec<Result<()>> = Vec::with_capacity(paths.len());
+        for (path, lookup) in paths.iter().zip(lookups) {
+            results.push(lookup.map(|lookup| match lookup {
+                Lookup::Fresh => {}
+                Lookup::Loaded(cached) => {
+                    self.files.insert(path.clone(), cached);
+                }
+                Lookup::Parsed(cached) => {
+                    if !self.transient.contains(path) {
+                        self.dirty.insert(path.clone());
+                    }
+                    self.files.insert(path.clone(), cached);
+                }
+            }));
+        }
+
+        paths
+            .iter()
+            .zip(results)
+            .map(|(path, result)| result.map(|()| &self.files[path].parsed))
+            .collect()
+    }
+
+    /// Cache file of `path`, if it exists and was written by this parser version
+    fn read_cached(&self, path: &Path) -> Option<CachedFile> {
+        let location = self.dir.as_ref()?.join(cache_file_name(path));
+        let bytes = std::fs::read(location).ok()?;
+        serde_json::from_slice::<CachedFile>(&bytes)
+            .ok()
+            .filter(|cached| cached.parser_version == PARSER_VERSION && cached.path == path)
+    }
+
+    /// Write the cache files of files parsed this run, removing those of deleted files
+    pub fn save(&mut self) -> Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        if self.dirty.is_empty() {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(dir).context("Failed to create cache directory")?;
+
+        // Write to a temporary file first so concurrent runs never read a partial cache file
+        let dirty: Vec<&PathBuf> = self.dirty.iter().collect();
+        dirty
+            .par_iter()
+            .map(|path| {
+                let name = cache_file_name(path);
+                let temp = dir.join(format!("{}.{}.tmp", name, std::process::id()));
+                std::fs::write(&temp, serde_json::to_vec(&self.files[*path])?).context("Failed to write cache")?;
+                std::fs::rename(&temp, dir.join(name)).context("Failed to replace cache")
+            })
+            .collect::<Result<()>>()?;
+
+        let index_path = dir.join(INDEX_FILE_NAME);
+        let mut index: BTreeMap<String, PathBuf> = std::fs::read(&index_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        for path in &self.dirty {
+            index.insert(cache_file_name(path), path.clone());
+        }
+        index.retain(|name, path| {
+            let exists = path.exists();
+            if !exists {
+                let _ = std::fs::remove_file(dir.join(name));
+            }
+            exists
+        });
+
+        let temp = dir.join(format!("{}.{}.tmp", INDEX_FILE_NAME, std::process::id()));
+        std::fs::write(&temp, serde_json::to_vec(&index)?).context("Failed to write cache index")?;
+        std::fs::rename(&temp, index_path).context("Failed to replace cache index")?;
+
+        self.dirty.clear();
+        Ok(())
+    }
+}
+
+/// Name of the cache file for a session file: a stable 64-bit FNV-1a hash of its path
+fn cache_file_name(path: &Path) -> String {
+    let hash = path
+        .as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}.json", hash)
+}
+
+/// Directory holding ccusage-rs cache files
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("ccusage-rs"))
+}
+
+/// Remove all cached data, returning the directory that was cleared
+pub fn clear_cache() -> Result<Option<PathBuf>> {
+    let Some(dir) = cache_dir() else {
+        return Ok(None);
+    };
+
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .with_context(|| format!("Failed to remove cache directory {}", dir.display()))?;
+    }
+
+    Ok(Some(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_records_are_reparsed_only_when_file_changes() {
+        let dir = TempDir::new("cache");
+        let path = dir.join("session.jsonl");
+        std::fs::write(&path, "first\n").unwrap();
+
+        let paths = vec![path.clone()];
+        let mut cache = EntryCache::disabled();
+        let parses = AtomicUsize::new(0);
+        let parse = |_: &Path| {
+            parses.fetch_add(1, Ordering::SeqCst);
+            Ok(FileRecords {
+                records: vec![Default::default()],
+                ..Default::default()
+            })
+        };
+
+        assert_eq!(cache.records_for_all(&paths, parse)[0].as_ref().unwrap().records.len(), 1);
+        assert_eq!(cache.records_for_all(&paths, parse)[0].as_ref().unwrap().records.len(), 1);
+
+        std::fs::write(&path, "first\nsecond\n").unwrap();
+        assert!(cache.records_for_all(&paths, parse)[0].is_ok());
+
+        assert_eq!(parses.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_cache_files_are_reused_across_runs_except_excluded_ones() {
+        let dir = TempDir::new("cache-dir");
+        let session = dir.join("session.jsonl");
+        let stdin = dir.join("stdin.jsonl");
+        std::fs::write(&session, "first\n").unwrap();
+        std::fs::write(&stdin, "first\n").unwrap();
+
+        let paths = vec![session.clone(), stdin.clone()];
+        let parses = AtomicUsize::new(0);
+        let parse = |_: &Path| {
+            parses.fetch_add(1, Ordering::SeqCst);
+            Ok(FileRecords::default())
+        };
+        let run = || {
+            let mut cache = EntryCache::in_dir(Some(dir.join(ENTRIES_DIR_NAME)));
+            cache.exclude(&stdin);
+            assert!(cache.records_for_all(&paths, parse).iter().all(|records| records.is_ok()));
+            cache.save().unwrap();
+        };
+
+        run();
+        assert_eq!(parses.load(Ordering::SeqCst), 2);
+        run();
+        assert_eq!(parses.load(Ordering::SeqCst), 3);
+
+        let cached: Vec<String> = std::fs::read_dir(dir.join(ENTRIES_DIR_NAME))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(cached.len(), 2);
+        assert!(cached.contains(&cache_file_name(&session)));
+    }
+}
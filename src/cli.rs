@@ -5,10 +5,18 @@
 //! ## Key Components
 //! - [`Args`] - Main CLI arguments structure
 //! - [`Commands`] - Subcommand definitions
+//! - [`CacheAction`] - Cache management actions
 //! - [`Plan`] - Claude plan type enumeration
 //! - [`SortOrder`] - Result sorting options
+//! - [`Breakdown`] - What `--breakdown` splits usage by
+//! - [`GroupBy`] - Aggregation dimensions for `report --group-by`
+//! - [`ToolSort`] - Ranking used by the `tools` report
+//! - [`UnknownModelPolicy`] - How models without pricing are costed
+//! - [`CostMode`] - Whether recorded `costUSD` or calculated costs are reported
 
 use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum Plan {
@@ -24,6 +32,70 @@ pub enum SortOrder {
     Desc,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Breakdown {
+    /// Per-model tokens and cost
+    Model,
+    /// Main conversation vs sidechain (Task subagent) tokens and cost
+    Sidechain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    /// Calendar day (local time)
+    Day,
+    /// ISO week, e.g. 2025-W23
+    Week,
+    Month,
+    /// Hour of a given day
+    Hour,
+    /// Full model name
+    Model,
+    Project,
+    /// Working directory recorded in each entry
+    Cwd,
+    Session,
+    /// Claude Code version
+    Version,
+    /// Main thread vs sidechain (Task subagents)
+    Sidechain,
+    /// API service tier: standard, priority or batch
+    Tier,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ToolSort {
+    /// Number of calls
+    Calls,
+    /// Attributed output tokens
+    Tokens,
+    /// Attributed cost
+    Cost,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UnknownModelPolicy {
+    /// Fail instead of reporting costs for models without pricing
+    Error,
+    /// Use fallback (Sonnet) rates and print a warning
+    Warn,
+    /// Count their usage as free
+    Zero,
+    /// Use fallback (Sonnet) rates without a warning
+    Fallback,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CostMode {
+    /// Use the cost recorded in the log when present, calculate it otherwise
+    Auto,
+    /// Always calculate costs from tokens
+    Calculate,
+    /// Only use recorded costs; entries without one count as free
+    Display,
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     /// Show daily usage reports (default)
@@ -44,9 +116,9 @@ pub enum Commands {
         #[arg(long)]
         json: bool,
         
-        /// Show per-model cost breakdown
-        #[arg(long)]
-        breakdown: bool,
+        /// Break usage down by model (default) or by main thread vs sidechain
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "model")]
+        breakdown: Option<Breakdown>,
         
         /// Show only recent entries (last N days)
         #[arg(long)]
@@ -70,9 +142,9 @@ pub enum Commands {
         #[arg(long)]
         json: bool,
         
-        /// Show per-model cost breakdown
-        #[arg(long)]
-        breakdown: bool,
+        /// Break usage down by model (default) or by main thread vs sidechain
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "model")]
+        breakdown: Option<Breakdown>,
     },
     /// Show individual session reports
     Session {
@@ -92,14 +164,139 @@ pub enum Commands {
         #[arg(long)]
         json: bool,
         
-        /// Show per-model cost breakdown
-        #[arg(long)]
-        breakdown: bool,
+        /// Break usage down by model (default) or by main thread vs sidechain
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "model")]
+        breakdown: Option<Breakdown>,
         
         /// Show only recent entries (last N days)
         #[arg(long)]
         recent: Option<usize>,
     },
+    /// Show usage aggregated per project
+    Projects {
+        /// Filter usage data from date (YYYYMMDD format)
+        #[arg(long)]
+        since: Option<String>,
+        
+        /// Filter usage data until date (YYYYMMDD format)
+        #[arg(long)]
+        until: Option<String>,
+        
+        /// Sort order for results (by cost)
+        #[arg(long, default_value = "desc", value_enum)]
+        order: SortOrder,
+        
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+        
+        /// Group by the working directory recorded in each entry instead of the project directory
+        #[arg(long)]
+        by_cwd: bool,
+        
+        /// Split usage into main thread vs sidechain (only `sidechain` is supported here)
+        #[arg(long, value_enum)]
+        breakdown: Option<Breakdown>,
+    },
+    /// Aggregate usage by any combination of dimensions, as nested groups
+    Report {
+        /// Dimensions to group by, outermost first (comma-separated or repeated)
+        #[arg(long, value_enum, value_delimiter = ',', required = true)]
+        group_by: Vec<GroupBy>,
+        
+        /// Filter usage data from date (YYYYMMDD format)
+        #[arg(long)]
+        since: Option<String>,
+        
+        /// Filter usage data until date (YYYYMMDD format)
+        #[arg(long)]
+        until: Option<String>,
+        
+        /// Sort order for top-level groups (by key for time and version, by cost otherwise)
+        #[arg(long, default_value = "desc", value_enum)]
+        order: SortOrder,
+        
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show tokens, cost and cache-hit ratio per Claude Code version
+    Versions {
+        /// Filter usage data from date (YYYYMMDD format)
+        #[arg(long)]
+        since: Option<String>,
+        
+        /// Filter usage data until date (YYYYMMDD format)
+        #[arg(long)]
+        until: Option<String>,
+        
+        /// Sort order for results (by version)
+        #[arg(long, default_value = "desc", value_enum)]
+        order: SortOrder,
+        
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show conversation branches per session: cost of the final path vs abandoned edits and rewinds
+    Branches {
+        /// Filter usage data from date (YYYYMMDD format)
+        #[arg(long)]
+        since: Option<String>,
+        
+        /// Filter usage data until date (YYYYMMDD format)
+        #[arg(long)]
+        until: Option<String>,
+        
+        /// Sort order for results (by discarded cost)
+        #[arg(long, default_value = "desc", value_enum)]
+        order: SortOrder,
+        
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+        
+        /// List the individual branches of sessions whose id starts with this prefix
+        #[arg(long)]
+        session: Option<String>,
+    },
+    /// Rank tools (including MCP tools) by calls, attributed output tokens and cost
+    Tools {
+        /// Filter usage data from date (YYYYMMDD format)
+        #[arg(long)]
+        since: Option<String>,
+        
+        /// Filter usage data until date (YYYYMMDD format)
+        #[arg(long)]
+        until: Option<String>,
+        
+        /// What to rank tools by
+        #[arg(long, default_value = "cost", value_enum)]
+        sort_by: ToolSort,
+        
+        /// Sort order for results
+        #[arg(long, default_value = "desc", value_enum)]
+        order: SortOrder,
+        
+        /// Combine MCP tools per server (`mcp__<server>`)
+        #[arg(long)]
+        by_server: bool,
+        
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+    /// Diagnose session files: skipped lines, undedupable and duplicate entries, unknown models
+    Doctor {
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+    /// Manage the on-disk cache of parsed session data
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
     /// Real-time monitoring (original behavior)
     Monitor {
         /// Claude plan type
@@ -122,12 +319,18 @@ pub enum Commands {
         #[arg(long)]
         recent: Option<usize>,
         
-        /// Update frequency in seconds (default: 2)
+        /// Redraw interval in seconds, also the rescan interval when file watching is unavailable (default: 2)
         #[arg(long, default_value = "2")]
         refresh_interval: u64,
     },
 }
 
+#[derive(Debug, Subcommand)]
+pub enum CacheAction {
+    /// Remove all cached data
+    Clear,
+}
+
 #[derive(Parser, Debug)]
 #[command(
     author,
@@ -139,15 +342,43 @@ pub struct Args {
     #[arg(long, global = true)]
     pub debug: bool,
     
-    /// Custom Claude directory path (can also use CLAUDE_CONFIG_DIR env var)
+    /// Custom Claude directory path, comma-separated for several (merged with CLAUDE_CONFIG_DIR env var)
     #[arg(long, global = true)]
     pub claude_dir: Option<String>,
     
-    /// Test new JSONL parser (legacy)
+    /// Read session data from a file, directory or glob instead of the Claude directories (repeatable, `-` for stdin)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub input: Vec<String>,
+    
+    /// Only report usage for the given project (path or directory name)
+    #[arg(long, global = true, conflicts_with = "here")]
+    pub project: Option<String>,
+    
+    /// Only report usage for the project in the current working directory
     #[arg(long, global = true)]
-    pub test_parser: bool,
+    pub here: bool,
+    
+    /// Re-read all session files instead of using the on-disk cache
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+    
+    /// Fail on the first malformed session line instead of skipping it
+    #[arg(long, global = true)]
+    pub strict: bool,
+    
+    /// Price models from a TOML or JSON pricing file instead of the one in the config directory
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "offline")]
+    pub pricing: Option<PathBuf>,
+    
+    /// Cost source: recorded `costUSD` when present (auto), always calculated, or recorded only (display)
+    #[arg(long, global = true, value_enum, default_value = "auto")]
+    pub mode: CostMode,
+    
+    /// How to cost models that neither the pricing file nor the built-in prices cover
+    #[arg(long, global = true, value_enum, default_value = "warn")]
+    pub unknown_model: UnknownModelPolicy,
     
-    /// Offline mode - use cached pricing and skip remote lookups
+    /// Offline mode - use built-in prices and the cached copy of the last pricing file, without reading pricing files
     #[arg(short = 'O', long, global = true)]
     pub offline: bool,
     
@@ -6,18 +6,33 @@
 //! - [`handle_daily_command`] - Process daily usage reports
 //! - [`handle_monthly_command`] - Process monthly usage aggregates
 //! - [`handle_session_command`] - Process individual session reports
+//! - [`handle_projects_command`] - Process per-project usage reports
+//! - [`handle_report_command`] - Process reports grouped by arbitrary dimensions
+//! - [`handle_versions_command`] - Process per-version usage reports
+//! - [`handle_branches_command`] - Process conversation branch reports
+//! - [`handle_tools_command`] - Process per-tool usage reports
+//! - [`handle_cache_command`] - Manage the on-disk cache
+//! - [`handle_doctor_command`] - Diagnose session files and data roots
 //! - [`handle_monitor_command`] - Real-time monitoring functionality
 
 use anyhow::{Context, Result};
 
-use crate::cli::{SortOrder};
+use crate::aggregation::{self, Group, UsageTotals};
+use crate::cache::clear_cache;
+use crate::conversation_tree::{self, SessionBranches};
+use crate::doctor::{self, DoctorReport};
+use crate::cli::{Breakdown, CacheAction, GroupBy, SortOrder, ToolSort};
 use crate::data_processing::{
-    filter_daily_stats_by_date, sort_daily_stats, aggregate_monthly_stats, sort_monthly_stats,
-    filter_sessions_by_date, sort_sessions, apply_recent_filter_daily, apply_recent_filter_sessions, MonthlyStats
+    filter_daily_stats_by_date, sort_daily_stats, sort_monthly_stats,
+    filter_sessions_by_date, sort_sessions, apply_recent_filter_daily, apply_recent_filter_sessions, MonthlyStats,
+    filter_entries_by_date, sort_project_stats, ProjectStats, parse_date_filter, VersionStats
 };
-use crate::table_display::{format_table_with_breakdown, generate_json_output};
+use crate::table_display::{SidechainSplit, format_long_context_note, format_table_with_breakdown, generate_json_output};
+use crate::tool_usage::{self, ToolStats};
+use crate::pricing::PricingSource;
 
 /// Helper function to format numbers with thousands separators
+#[allow(clippy::manual_is_multiple_of)]
 fn format_number(n: u64) -> String {
     let mut result = String::new();
     let s = n.to_string();
@@ -32,43 +47,24 @@ fn format_number(n: u64) -> String {
     
     result
 }
-use crate::{entry_processor, jsonl_parser};
+use crate::data_sources::DataSourceOptions;
+use crate::entry_processor;
 
 /// Handle daily usage reports command
 pub fn handle_daily_command(
+    sources: &DataSourceOptions,
     since: Option<&str>,
     until: Option<&str>,
     order: SortOrder,
     json: bool,
-    breakdown: bool,
+    breakdown: Option<Breakdown>,
     recent: Option<usize>,
 ) -> Result<()> {
-    // Get current working directory for project lookup
-    let cwd = std::env::current_dir().context("Failed to get current directory")?;
-    let project_dirs = jsonl_parser::get_all_project_dirs(&cwd);
-
-    if project_dirs.is_empty() {
-        anyhow::bail!(
-            "No Claude session data found. Make sure you're in a project directory that has been used with Claude Code."
-        );
-    }
-
-    // Find all JSONL session files from all project directories
-    let mut session_files = Vec::new();
-    for project_dir in &project_dirs {
-        let files = jsonl_parser::find_session_files(project_dir, None)
-            .context("Failed to find session files")?;
-        session_files.extend(files);
-    }
-
-    if session_files.is_empty() {
-        anyhow::bail!(
-            "No JSONL session files found in project directories. This project may not have any Claude Code usage yet."
-        );
-    }
+    // Find all JSONL session files across every Claude data root
+    let session_files = sources.discover_session_files()?;
 
     // Process all entries with global entry-level deduplication
-    let daily_stats = entry_processor::process_all_entries(&session_files)
+    let daily_stats = entry_processor::process_all_entries(&session_files, sources)
         .context("Failed to process entries and aggregate daily statistics")?;
 
     if daily_stats.is_empty() {
@@ -99,7 +95,13 @@ pub fn handle_daily_command(
         println!("{}", serde_json::to_string_pretty(&json_output)?);
     } else {
         // Display the table
-        let table_output = format_table_with_breakdown(&sorted_stats, breakdown);
+        let table_output = match breakdown {
+            Some(Breakdown::Sidechain) => format_sidechain_table(
+                "Date",
+                sorted_stats.iter().map(|stats| (stats.date.clone(), stats.sidechain)),
+            ),
+            _ => format_table_with_breakdown(&sorted_stats, breakdown.is_some()),
+        };
         println!("{}", table_output);
     }
 
@@ -108,63 +110,36 @@ pub fn handle_daily_command(
 
 /// Handle monthly usage aggregates command
 pub fn handle_monthly_command(
+    sources: &DataSourceOptions,
     since: Option<&str>,
     until: Option<&str>,
     order: SortOrder,
     json: bool,
-    breakdown: bool,
+    breakdown: Option<Breakdown>,
 ) -> Result<()> {
-    // Get current working directory for project lookup
-    let cwd = std::env::current_dir().context("Failed to get current directory")?;
-    let project_dirs = jsonl_parser::get_all_project_dirs(&cwd);
-
-    if project_dirs.is_empty() {
-        anyhow::bail!(
-            "No Claude session data found. Make sure you're in a project directory that has been used with Claude Code."
-        );
-    }
+    // Find all JSONL session files across every Claude data root
+    let session_files = sources.discover_session_files()?;
 
-    // Find all JSONL session files from all project directories
-    let mut session_files = Vec::new();
-    for project_dir in &project_dirs {
-        let files = jsonl_parser::find_session_files(project_dir, None)
-            .context("Failed to find session files")?;
-        session_files.extend(files);
-    }
+    // Load all entries with global entry-level deduplication
+    let entries = entry_processor::load_all_entries(&session_files, sources)
+        .context("Failed to process entries")?;
 
-    if session_files.is_empty() {
-        anyhow::bail!(
-            "No JSONL session files found in project directories. This project may not have any Claude Code usage yet."
-        );
-    }
-
-    // Process all entries to get daily stats first
-    let daily_stats = entry_processor::process_all_entries(&session_files)
-        .context("Failed to process entries and aggregate daily statistics")?;
-
-    if daily_stats.is_empty() {
+    if entries.is_empty() {
         anyhow::bail!(
             "No valid usage data found. The JSONL files may be corrupted or in an unexpected format."
         );
     }
 
-    // Apply date filtering to daily stats first
-    let filtered_daily_stats = filter_daily_stats_by_date(daily_stats, since, until)
-        .context("Failed to filter daily stats by date range")?;
+    // Apply date filtering before aggregation
+    let filtered_entries = filter_entries_by_date(entries, since, until)
+        .context("Failed to filter entries by date range")?;
     
-    if filtered_daily_stats.is_empty() {
+    if filtered_entries.is_empty() {
         println!("No data found for the specified date range.");
         return Ok(());
     }
 
-    // Aggregate into monthly stats
-    let monthly_stats = aggregate_monthly_stats(&filtered_daily_stats)
-        .context("Failed to aggregate monthly statistics")?;
-    
-    if monthly_stats.is_empty() {
-        println!("No monthly data found for the specified date range.");
-        return Ok(());
-    }
+    let monthly_stats = entry_processor::aggregate_entries_by_month(filtered_entries);
     
     // Apply sorting
     let sorted_monthly = sort_monthly_stats(monthly_stats, order);
@@ -176,7 +151,13 @@ pub fn handle_monthly_command(
         println!("{}", serde_json::to_string_pretty(&json_output)?);
     } else {
         // Display the table
-        let table_output = format_monthly_table_with_breakdown(&sorted_monthly, breakdown);
+        let table_output = match breakdown {
+            Some(Breakdown::Sidechain) => format_sidechain_table(
+                "Month",
+                sorted_monthly.iter().map(|stat| (stat.month.clone(), stat.sidechain)),
+            ),
+            _ => format_monthly_table_with_breakdown(&sorted_monthly, breakdown.is_some()),
+        };
         println!("{}", table_output);
     }
 
@@ -185,44 +166,20 @@ pub fn handle_monthly_command(
 
 /// Handle individual session reports command
 pub fn handle_session_command(
+    sources: &DataSourceOptions,
     since: Option<&str>,
     until: Option<&str>,
     order: SortOrder,
     json: bool,
-    breakdown: bool,
+    breakdown: Option<Breakdown>,
     recent: Option<usize>,
 ) -> Result<()> {
-    // Get current working directory for project lookup
-    let cwd = std::env::current_dir().context("Failed to get current directory")?;
-    let project_dirs = jsonl_parser::get_all_project_dirs(&cwd);
+    // Find all JSONL session files across every Claude data root
+    let session_files = sources.discover_session_files()?;
 
-    if project_dirs.is_empty() {
-        anyhow::bail!(
-            "No Claude session data found. Make sure you're in a project directory that has been used with Claude Code."
-        );
-    }
-
-    // Find all JSONL session files from all project directories
-    let mut session_files = Vec::new();
-    for project_dir in &project_dirs {
-        let files = jsonl_parser::find_session_files(project_dir, None)
-            .context("Failed to find session files")?;
-        session_files.extend(files);
-    }
-
-    if session_files.is_empty() {
-        anyhow::bail!(
-            "No JSONL session files found in project directories. This project may not have any Claude Code usage yet."
-        );
-    }
-
-    // Parse all session files to get sessions
-    let mut all_sessions = Vec::new();
-    for file in &session_files {
-        let session_data = jsonl_parser::parse_session_file(file)
-            .context("Failed to parse session file")?;
-        all_sessions.push(session_data);
-    }
+    // Build sessions from globally deduplicated entries, like the other reports
+    let all_sessions = entry_processor::load_sessions(&session_files, sources)
+        .context("Failed to process sessions")?;
 
     if all_sessions.is_empty() {
         anyhow::bail!(
@@ -252,13 +209,342 @@ pub fn handle_session_command(
         println!("{}", serde_json::to_string_pretty(&json_output)?);
     } else {
         // Display the table
-        let table_output = format_session_table_with_breakdown(&sorted_sessions, breakdown);
+        let table_output = match breakdown {
+            Some(Breakdown::Sidechain) => format_sidechain_table(
+                "Session ID",
+                sorted_sessions.iter().map(|session| (short_session_id(&session.session_id), session.sidechain)),
+            ),
+            _ => format_session_table_with_breakdown(&sorted_sessions, breakdown.is_some()),
+        };
+        println!("{}", table_output);
+    }
+
+    Ok(())
+}
+
+/// Handle per-project usage reports command
+pub fn handle_projects_command(
+    sources: &DataSourceOptions,
+    since: Option<&str>,
+    until: Option<&str>,
+    order: SortOrder,
+    json: bool,
+    by_cwd: bool,
+    breakdown: Option<Breakdown>,
+) -> Result<()> {
+    if breakdown == Some(Breakdown::Model) {
+        anyhow::bail!("The projects report only supports `--breakdown sidechain`");
+    }
+
+    // Find all JSONL session files across every Claude data root
+    let session_files = sources.discover_session_files()?;
+
+    // Load all entries with global entry-level deduplication
+    let entries = entry_processor::load_all_entries(&session_files, sources)
+        .context("Failed to process entries")?;
+
+    if entries.is_empty() {
+        anyhow::bail!(
+            "No valid usage data found. The JSONL files may be corrupted or in an unexpected format."
+        );
+    }
+
+    // Apply date filtering before aggregation so activity ranges respect it
+    let filtered_entries = filter_entries_by_date(entries, since, until)
+        .context("Failed to filter entries by date range")?;
+
+    if filtered_entries.is_empty() {
+        println!("No data found for the specified date range.");
+        return Ok(());
+    }
+
+    let project_stats = entry_processor::aggregate_entries_by_project(filtered_entries, by_cwd);
+
+    // Apply sorting
+    let sorted_projects = sort_project_stats(project_stats, order);
+
+    if json {
+        // Output in JSON format
+        let json_output = generate_projects_json_output(&sorted_projects)
+            .context("Failed to generate JSON output")?;
+        println!("{}", serde_json::to_string_pretty(&json_output)?);
+    } else {
+        // Display the table
+        let table_output = match breakdown {
+            Some(Breakdown::Sidechain) => format_sidechain_table(
+                "Project",
+                sorted_projects.iter().map(|stat| (stat.path.clone(), stat.sidechain)),
+            ),
+            _ => format_projects_table(&sorted_projects),
+        };
         println!("{}", table_output);
     }
 
     Ok(())
 }
 
+/// Handle reports grouped by any combination of dimensions
+pub fn handle_report_command(
+    sources: &DataSourceOptions,
+    group_by: &[GroupBy],
+    since: Option<&str>,
+    until: Option<&str>,
+    order: SortOrder,
+    json: bool,
+) -> Result<()> {
+    for (index, dimension) in group_by.iter().enumerate() {
+        if group_by[..index].contains(dimension) {
+            anyhow::bail!("--group-by lists '{}' more than once", format!("{:?}", dimension).to_lowercase());
+        }
+    }
+
+    // Find all JSONL session files across every Claude data root
+    let session_files = sources.discover_session_files()?;
+
+    // Load all entries with global entry-level deduplication
+    let entries = entry_processor::load_all_entries(&session_files, sources)
+        .context("Failed to process entries")?;
+
+    if entries.is_empty() {
+        anyhow::bail!(
+            "No valid usage data found. The JSONL files may be corrupted or in an unexpected format."
+        );
+    }
+
+    let filtered_entries = filter_entries_by_date(entries, since, until)
+        .context("Failed to filter entries by date range")?;
+
+    if filtered_entries.is_empty() {
+        println!("No data found for the specified date range.");
+        return Ok(());
+    }
+
+    let mut groups = aggregation::group_entries(&filtered_entries, group_by);
+    aggregation::sort_groups(&mut groups, order);
+    let totals = aggregation::total_usage(&filtered_entries);
+
+    if json {
+        let json_output = serde_json::json!({
+            "group_by": group_by,
+            "groups": groups,
+            "totals": totals,
+        });
+        println!("{}", serde_json::to_string_pretty(&json_output)?);
+    } else {
+        println!("{}", format_group_table(group_by, &groups, &totals));
+    }
+
+    Ok(())
+}
+
+/// Handle per-version usage reports command
+pub fn handle_versions_command(
+    sources: &DataSourceOptions,
+    since: Option<&str>,
+    until: Option<&str>,
+    order: SortOrder,
+    json: bool,
+) -> Result<()> {
+    // Find all JSONL session files across every Claude data root
+    let session_files = sources.discover_session_files()?;
+
+    // Load all entries with global entry-level deduplication
+    let entries = entry_processor::load_all_entries(&session_files, sources)
+        .context("Failed to process entries")?;
+
+    if entries.is_empty() {
+        anyhow::bail!(
+            "No valid usage data found. The JSONL files may be corrupted or in an unexpected format."
+        );
+    }
+
+    let filtered_entries = filter_entries_by_date(entries, since, until)
+        .context("Failed to filter entries by date range")?;
+
+    if filtered_entries.is_empty() {
+        println!("No data found for the specified date range.");
+        return Ok(());
+    }
+
+    // Aggregated oldest release first
+    let mut version_stats = entry_processor::aggregate_entries_by_version(filtered_entries);
+    if matches!(order, SortOrder::Desc) {
+        version_stats.reverse();
+    }
+
+    if json {
+        let json_output = generate_versions_json_output(&version_stats)
+            .context("Failed to generate JSON output")?;
+        println!("{}", serde_json::to_string_pretty(&json_output)?);
+    } else {
+        println!("{}", format_versions_table(&version_stats));
+    }
+
+    Ok(())
+}
+
+/// Handle conversation branch reports command
+pub fn handle_branches_command(
+    sources: &DataSourceOptions,
+    since: Option<&str>,
+    until: Option<&str>,
+    order: SortOrder,
+    json: bool,
+    session_prefix: Option<&str>,
+) -> Result<()> {
+    // Find all JSONL session files across every Claude data root
+    let session_files = sources.discover_session_files()?;
+
+    let all_sessions = conversation_tree::load_session_branches(&session_files, sources)
+        .context("Failed to build conversation trees")?;
+
+    if all_sessions.is_empty() {
+        anyhow::bail!(
+            "No valid session data found. The JSONL files may be corrupted or in an unexpected format."
+        );
+    }
+
+    // Apply date filtering on the session start, like the session report
+    let since_date = since.map(parse_date_filter).transpose()?;
+    let until_date = until.map(parse_date_filter).transpose()?;
+    let mut sessions: Vec<SessionBranches> = all_sessions
+        .into_iter()
+        .filter(|session| {
+            let date = session.start.with_timezone(&chrono::Local).date_naive();
+            since_date.is_none_or(|since| date >= since) && until_date.is_none_or(|until| date <= until)
+        })
+        .filter(|session| session_prefix.is_none_or(|prefix| session.session_id.starts_with(prefix)))
+        .collect();
+
+    if sessions.is_empty() {
+        println!("No sessions found for the specified filters.");
+        return Ok(());
+    }
+
+    // Apply sorting
+    sessions.sort_by(|a, b| {
+        let ordering = a.discarded_cost().partial_cmp(&b.discarded_cost()).unwrap_or(std::cmp::Ordering::Equal);
+        match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    });
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "sessions": sessions }))?);
+    } else if session_prefix.is_some() {
+        for session in &sessions {
+            println!("{}", format_branch_list(session));
+        }
+    } else {
+        println!("{}", format_branches_table(&sessions));
+    }
+
+    Ok(())
+}
+
+/// Handle per-tool usage reports command
+pub fn handle_tools_command(
+    sources: &DataSourceOptions,
+    since: Option<&str>,
+    until: Option<&str>,
+    sort_by: ToolSort,
+    order: SortOrder,
+    by_server: bool,
+    json: bool,
+) -> Result<()> {
+    // Find all JSONL session files across every Claude data root
+    let session_files = sources.discover_session_files()?;
+
+    let all_turns = tool_usage::load_tool_turns(&session_files, sources)
+        .context("Failed to read tool calls")?;
+
+    if all_turns.is_empty() {
+        anyhow::bail!(
+            "No valid usage data found. The JSONL files may be corrupted or in an unexpected format."
+        );
+    }
+
+    // Apply date filtering on each turn's local date
+    let since_date = since.map(parse_date_filter).transpose()?;
+    let until_date = until.map(parse_date_filter).transpose()?;
+    let turns: Vec<_> = all_turns
+        .into_iter()
+        .filter(|turn| {
+            let date = turn.timestamp.with_timezone(&chrono::Local).date_naive();
+            since_date.is_none_or(|since| date >= since) && until_date.is_none_or(|until| date <= until)
+        })
+        .collect();
+
+    let mut stats = tool_usage::aggregate_tool_usage(&turns, by_server);
+    if stats.is_empty() {
+        println!("No tool calls found for the specified date range.");
+        return Ok(());
+    }
+
+    // Apply sorting
+    stats.sort_by(|a, b| {
+        let ordering = match sort_by {
+            ToolSort::Calls => a.calls.cmp(&b.calls),
+            ToolSort::Tokens => a.output_tokens.cmp(&b.output_tokens),
+            ToolSort::Cost => a.cost_usd.partial_cmp(&b.cost_usd).unwrap_or(std::cmp::Ordering::Equal),
+        };
+        match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    });
+
+    let total_cost = turns.iter().fold(0.0, |total, turn| total + turn.cost_usd);
+    if json {
+        let json_output = serde_json::json!({
+            "tools": stats,
+            "total_turns": turns.len(),
+            "tool_turns": turns.iter().filter(|turn| !turn.tools.is_empty()).count(),
+            "total_cost_usd": total_cost,
+        });
+        println!("{}", serde_json::to_string_pretty(&json_output)?);
+    } else {
+        println!("{}", format_tools_table(&stats, total_cost, by_server));
+    }
+
+    Ok(())
+}
+
+/// Handle cache management command
+pub fn handle_cache_command(action: CacheAction) -> Result<()> {
+    match action {
+        CacheAction::Clear => match clear_cache()? {
+            Some(dir) => println!("Cleared cache at {}", dir.display()),
+            None => println!("No cache directory available on this platform."),
+        },
+    }
+
+    Ok(())
+}
+
+/// Handle doctor command: report parsing problems per file and the resolved data roots
+pub fn handle_doctor_command(sources: &DataSourceOptions, json: bool) -> Result<()> {
+    // Unlike the reports, keep going without data so the roots can still be shown
+    let report = if sources.inputs.is_empty() {
+        let project_dirs = sources.project_dirs();
+        let session_files = sources.session_files(&project_dirs)?;
+        doctor::diagnose(sources.roots(), project_dirs.len(), &session_files)
+    } else {
+        let mut report = doctor::diagnose(Vec::new(), 0, &sources.input_files()?);
+        report.inputs = sources.inputs.clone();
+        report
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{}", format_doctor_report(&report));
+    }
+
+    Ok(())
+}
+
 /// Generate JSON output for monthly statistics
 pub fn generate_monthly_json_output(stats: &[MonthlyStats]) -> Result<serde_json::Value> {
     let json_obj = serde_json::json!({
@@ -269,9 +555,15 @@ pub fn generate_monthly_json_output(stats: &[MonthlyStats]) -> Result<serde_json
                 "input_tokens": stat.input_tokens,
                 "output_tokens": stat.output_tokens,
                 "cache_creation_tokens": stat.cache_creation_tokens,
+                "cache_creation_5m_tokens": stat.cache_creation_tokens - stat.cache_creation_1h_tokens,
+                "cache_creation_1h_tokens": stat.cache_creation_1h_tokens,
                 "cache_read_tokens": stat.cache_read_tokens,
                 "total_tokens": stat.total_tokens,
-                "cost_usd": stat.cost_usd
+                "cost_usd": stat.cost_usd,
+                "sidechain": stat.sidechain,
+                "pricing_source": stat.pricing_source,
+                "long_context_cost_usd": stat.long_context_cost_usd,
+                "long_context_requests": stat.long_context_requests
             })
         }).collect::<Vec<_>>()
     });
@@ -287,7 +579,10 @@ pub fn generate_session_json_output(sessions: &[crate::jsonl_parser::SessionData
             "start_time": session.start_time,
             "end_time": session.end_time,
             "model_usage": session.model_usage,
-            "total_weighted_tokens": session.total_weighted_tokens
+            "total_weighted_tokens": session.total_weighted_tokens,
+            "sidechain": session.sidechain,
+            "pricing_source": session_pricing_source(session),
+            "long_context_cost_usd": session.long_context_cost_usd
         })
     }).collect();
     
@@ -298,6 +593,58 @@ pub fn generate_session_json_output(sessions: &[crate::jsonl_parser::SessionData
     Ok(json_obj)
 }
 
+/// Generate JSON output for project statistics
+pub fn generate_projects_json_output(stats: &[ProjectStats]) -> Result<serde_json::Value> {
+    let json_obj = serde_json::json!({
+        "projects": stats.iter().map(|stat| {
+            serde_json::json!({
+                "project": stat.project,
+                "path": stat.path,
+                "models": stat.models,
+                "input_tokens": stat.input_tokens,
+                "output_tokens": stat.output_tokens,
+                "cache_creation_tokens": stat.cache_creation_tokens,
+                "cache_read_tokens": stat.cache_read_tokens,
+                "total_tokens": stat.total_tokens,
+                "cost_usd": stat.cost_usd,
+                "session_count": stat.session_count,
+                "sidechain": stat.sidechain,
+                "pricing_source": stat.pricing_source,
+                "long_context_cost_usd": stat.long_context_cost_usd,
+                "first_activity": stat.first_activity.to_rfc3339(),
+                "last_activity": stat.last_activity.to_rfc3339()
+            })
+        }).collect::<Vec<_>>()
+    });
+    
+    Ok(json_obj)
+}
+
+/// Generate JSON output for per-version statistics
+pub fn generate_versions_json_output(stats: &[VersionStats]) -> Result<serde_json::Value> {
+    let json_obj = serde_json::json!({
+        "versions": stats.iter().map(|stat| {
+            serde_json::json!({
+                "version": stat.version,
+                "entry_count": stat.entry_count,
+                "input_tokens": stat.input_tokens,
+                "output_tokens": stat.output_tokens,
+                "cache_creation_tokens": stat.cache_creation_tokens,
+                "cache_read_tokens": stat.cache_read_tokens,
+                "total_tokens": stat.total_tokens,
+                "cache_hit_ratio": stat.cache_hit_ratio(),
+                "cost_usd": stat.cost_usd,
+                "pricing_source": stat.pricing_source,
+                "long_context_cost_usd": stat.long_context_cost_usd,
+                "first_seen": stat.first_seen.to_rfc3339(),
+                "last_seen": stat.last_seen.to_rfc3339()
+            })
+        }).collect::<Vec<_>>()
+    });
+    
+    Ok(json_obj)
+}
+
 /// Format monthly table with optional breakdown
 pub fn format_monthly_table_with_breakdown(stats: &[MonthlyStats], breakdown: bool) -> String {
     if breakdown {
@@ -314,32 +661,37 @@ pub fn format_monthly_table_standard(stats: &[MonthlyStats]) -> String {
     let mut output = String::new();
     
     // Header
-    output.push_str("┌─────────┬─────────────┬──────────────┬───────────────┬──────────────┬─────────────┬──────────────┬─────────────┐\n");
-    output.push_str("│ Month   │ Models      │ Input Tokens │ Output Tokens │ Cache Create │ Cache Read  │ Total Tokens │ Cost (USD)  │\n");
-    output.push_str("├─────────┼─────────────┼──────────────┼───────────────┼──────────────┼─────────────┼──────────────┼─────────────┤\n");
+    output.push_str("┌─────────┬─────────────┬──────────────┬───────────────┬──────────────┬──────────────┬─────────────┬──────────────┬─────────────┐\n");
+    output.push_str("│ Month   │ Models      │ Input Tokens │ Output Tokens │     Cache 5m │     Cache 1h │ Cache Read  │ Total Tokens │ Cost (USD)  │\n");
+    output.push_str("├─────────┼─────────────┼──────────────┼───────────────┼──────────────┼──────────────┼─────────────┼──────────────┼─────────────┤\n");
     
     // Data rows
     for stat in stats {
         output.push_str(&format!(
-            "│ {:<7} │ {:<11} │ {:>12} │ {:>13} │ {:>12} │ {:>11} │ {:>12} │ {:>11.2} │\n",
+            "│ {:<7} │ {:<11} │ {:>12} │ {:>13} │ {:>12} │ {:>12} │ {:>11} │ {:>12} │ {:>11} │\n",
             stat.month,
             stat.models.join(", "),
             format_number(stat.input_tokens),
             format_number(stat.output_tokens),
-            format_number(stat.cache_creation_tokens),
+            format_number(stat.cache_creation_tokens - stat.cache_creation_1h_tokens),
+            format_number(stat.cache_creation_1h_tokens),
             format_number(stat.cache_read_tokens),
             format_number(stat.total_tokens),
-            stat.cost_usd
+            format_cost(stat.cost_usd, stat.pricing_source)
         ));
     }
     
-    output.push_str("└─────────┴─────────────┴──────────────┴───────────────┴──────────────┴─────────────┴──────────────┴─────────────┘\n");
+    output.push_str("└─────────┴─────────────┴──────────────┴───────────────┴──────────────┴──────────────┴─────────────┴──────────────┴─────────────┘\n");
     
     // Calculate totals
     let total_tokens: u64 = stats.iter().map(|s| s.total_tokens).sum();
     let total_cost: f64 = stats.iter().map(|s| s.cost_usd).sum();
+    let long_context_cost = stats.iter().fold(0.0, |total, s| total + s.long_context_cost_usd);
     
     output.push_str(&format!("\nTotal Usage: {} tokens | Total Cost: ${:.2}", format_number(total_tokens), total_cost));
+    if let Some(note) = format_long_context_note(long_context_cost, total_cost) {
+        output.push_str(&format!("\n{}", note));
+    }
     
     output
 }
@@ -362,9 +714,9 @@ pub fn format_session_table_standard(sessions: &[crate::jsonl_parser::SessionDat
     let mut output = String::new();
     
     // Header
-    output.push_str("┌──────────────┬─────────────────────┬─────────────┬──────────────┬─────────────┐\n");
-    output.push_str("│ Session ID   │ Start Time          │ Models      │ Total Tokens │ Cost (USD)  │\n");
-    output.push_str("├──────────────┼─────────────────────┼─────────────┼──────────────┼─────────────┤\n");
+    output.push_str("┌──────────────┬─────────────────────┬─────────────┬──────────────┬──────────────┬──────────────┬─────────────┐\n");
+    output.push_str("│ Session ID   │ Start Time          │ Models      │     Cache 5m │     Cache 1h │ Total Tokens │ Cost (USD)  │\n");
+    output.push_str("├──────────────┼─────────────────────┼─────────────┼──────────────┼──────────────┼──────────────┼─────────────┤\n");
     
     // Data rows
     for session in sessions {
@@ -375,26 +727,24 @@ pub fn format_session_table_standard(sessions: &[crate::jsonl_parser::SessionDat
         let total_tokens: u64 = session.model_usage.values()
             .map(|usage| usage.total_input + usage.total_output + usage.total_cache_write + usage.total_cache_read)
             .sum();
+        let cache_write_5m: u64 = session.model_usage.values().map(|usage| usage.total_cache_write_5m).sum();
+        let cache_write_1h: u64 = session.model_usage.values().map(|usage| usage.total_cache_write_1h).sum();
         
         let cost = calculate_session_cost(&session.model_usage);
         
-        let short_session_id = if session.session_id.len() > 12 {
-            format!("{}...", &session.session_id[0..12])
-        } else {
-            session.session_id.clone()
-        };
-        
         output.push_str(&format!(
-            "│ {:<12} │ {:<19} │ {:<11} │ {:>12} │ {:>11.2} │\n",
-            short_session_id,
+            "│ {:<12} │ {:<19} │ {:<11} │ {:>12} │ {:>12} │ {:>12} │ {:>11} │\n",
+            short_session_id(&session.session_id),
             session.start_time.format("%Y-%m-%d %H:%M").to_string(),
             models.join(", "),
+            format_number(cache_write_5m),
+            format_number(cache_write_1h),
             format_number(total_tokens),
-            cost
+            format_cost(cost, session_pricing_source(session))
         ));
     }
     
-    output.push_str("└──────────────┴─────────────────────┴─────────────┴──────────────┴─────────────┘\n");
+    output.push_str("└──────────────┴─────────────────────┴─────────────┴──────────────┴──────────────┴──────────────┴─────────────┘\n");
     
     // Calculate totals
     let total_tokens: u64 = sessions.iter().map(|s| {
@@ -406,8 +756,447 @@ pub fn format_session_table_standard(sessions: &[crate::jsonl_parser::SessionDat
     let total_cost: f64 = sessions.iter()
         .map(|s| calculate_session_cost(&s.model_usage))
         .sum();
+    let long_context_cost = sessions.iter().fold(0.0, |total, s| total + s.long_context_cost_usd);
+    
+    output.push_str(&format!("\nTotal Usage: {} tokens | Total Cost: ${:.2}", format_number(total_tokens), total_cost));
+    if let Some(note) = format_long_context_note(long_context_cost, total_cost) {
+        output.push_str(&format!("\n{}", note));
+    }
+    
+    output
+}
+
+/// Format per-project table
+pub fn format_projects_table(stats: &[ProjectStats]) -> String {
+    let mut output = String::new();
+    
+    // Header
+    output.push_str("┌────────────────────────────────┬──────────┬──────────────┬─────────────┬──────────────────┬──────────────────┐\n");
+    output.push_str("│ Project                        │ Sessions │ Total Tokens │ Cost (USD)  │ First Activity   │ Last Activity    │\n");
+    output.push_str("├────────────────────────────────┼──────────┼──────────────┼─────────────┼──────────────────┼──────────────────┤\n");
+    
+    // Data rows
+    for stat in stats {
+        output.push_str(&format!(
+            "│ {:<30} │ {:>8} │ {:>12} │ {:>11} │ {:<16} │ {:<16} │\n",
+            shorten_path(&stat.path, 30),
+            stat.session_count,
+            format_number(stat.total_tokens),
+            format_cost(stat.cost_usd, stat.pricing_source),
+            stat.first_activity.format("%Y-%m-%d %H:%M").to_string(),
+            stat.last_activity.format("%Y-%m-%d %H:%M").to_string()
+        ));
+    }
+    
+    output.push_str("└────────────────────────────────┴──────────┴──────────────┴─────────────┴──────────────────┴──────────────────┘\n");
+    
+    // Calculate totals
+    let total_tokens: u64 = stats.iter().map(|s| s.total_tokens).sum();
+    let total_cost: f64 = stats.iter().map(|s| s.cost_usd).sum();
+    let long_context_cost = stats.iter().fold(0.0, |total, s| total + s.long_context_cost_usd);
     
     output.push_str(&format!("\nTotal Usage: {} tokens | Total Cost: ${:.2}", format_number(total_tokens), total_cost));
+    if let Some(note) = format_long_context_note(long_context_cost, total_cost) {
+        output.push_str(&format!("\n{}", note));
+    }
+    
+    output
+}
+
+/// Width of the group column in grouped reports, including indentation
+const GROUP_COLUMN_WIDTH: usize = 40;
+
+/// Format nested groups, indenting each level under its parent
+pub fn format_group_table(group_by: &[GroupBy], groups: &[Group], totals: &UsageTotals) -> String {
+    let mut output = String::new();
+    let heading = group_by
+        .iter()
+        .map(|dimension| format!("{:?}", dimension))
+        .collect::<Vec<_>>()
+        .join(" > ");
+    
+    // Header
+    output.push_str(&format!("┌{}┬──────────┬──────────────┬───────────────┬──────────────┬──────────────┬──────────────┬──────────────┬─────────────┐\n", "─".repeat(GROUP_COLUMN_WIDTH + 2)));
+    output.push_str(&format!(
+        "│ {:<width$} │ Requests │ Input Tokens │ Output Tokens │     Cache 5m │     Cache 1h │   Cache Read │ Total Tokens │ Cost (USD)  │\n",
+        shorten_path(&heading, GROUP_COLUMN_WIDTH),
+        width = GROUP_COLUMN_WIDTH
+    ));
+    
+    // Data rows, with a separator above each top-level group
+    for group in groups {
+        output.push_str(&format!("├{}┼──────────┼──────────────┼───────────────┼──────────────┼──────────────┼──────────────┼──────────────┼─────────────┤\n", "─".repeat(GROUP_COLUMN_WIDTH + 2)));
+        push_group_rows(&mut output, group, 0);
+    }
+    
+    output.push_str(&format!("└{}┴──────────┴──────────────┴───────────────┴──────────────┴──────────────┴──────────────┴──────────────┴─────────────┘\n", "─".repeat(GROUP_COLUMN_WIDTH + 2)));
+    
+    output.push_str(&format!(
+        "\nTotal Usage: {} tokens | Total Cost: ${:.2}",
+        format_number(totals.total_tokens),
+        totals.cost_usd
+    ));
+    if let Some(note) = format_long_context_note(totals.long_context_cost_usd, totals.cost_usd) {
+        output.push_str(&format!("\n{}", note));
+    }
+    
+    output
+}
+
+fn push_group_rows(output: &mut String, group: &Group, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let label = shorten_path(&group.key, GROUP_COLUMN_WIDTH.saturating_sub(indent.len()).max(8));
+    
+    output.push_str(&format!(
+        "│ {:<width$} │ {:>8} │ {:>12} │ {:>13} │ {:>12} │ {:>12} │ {:>12} │ {:>12} │ {:>11} │\n",
+        format!("{}{}", indent, label),
+        format_number(group.totals.entry_count as u64),
+        format_number(group.totals.input_tokens),
+        format_number(group.totals.output_tokens),
+        format_number(group.totals.cache_creation_5m_tokens),
+        format_number(group.totals.cache_creation_1h_tokens),
+        format_number(group.totals.cache_read_tokens),
+        format_number(group.totals.total_tokens),
+        format_cost(group.totals.cost_usd, group.totals.pricing_source),
+        width = GROUP_COLUMN_WIDTH
+    ));
+    
+    for child in &group.groups {
+        push_group_rows(output, child, depth + 1);
+    }
+}
+
+/// Format per-version table
+pub fn format_versions_table(stats: &[VersionStats]) -> String {
+    let mut output = String::new();
+    
+    // Header
+    output.push_str("┌────────────┬──────────┬──────────────┬───────────────┬──────────────┬──────────────┬───────────┬─────────────┬────────────┬────────────┐\n");
+    output.push_str("│ Version    │ Requests │ Input Tokens │ Output Tokens │ Cache Create │   Cache Read │ Cache Hit │ Cost (USD)  │ First Seen │ Last Seen  │\n");
+    output.push_str("├────────────┼──────────┼──────────────┼───────────────┼──────────────┼──────────────┼───────────┼─────────────┼────────────┼────────────┤\n");
+    
+    // Data rows
+    for stat in stats {
+        output.push_str(&format!(
+            "│ {:<10} │ {:>8} │ {:>12} │ {:>13} │ {:>12} │ {:>12} │ {:>8.1}% │ {:>11} │ {:<10} │ {:<10} │\n",
+            stat.version,
+            format_number(stat.entry_count as u64),
+            format_number(stat.input_tokens),
+            format_number(stat.output_tokens),
+            format_number(stat.cache_creation_tokens),
+            format_number(stat.cache_read_tokens),
+            stat.cache_hit_ratio() * 100.0,
+            format_cost(stat.cost_usd, stat.pricing_source),
+            stat.first_seen.format("%Y-%m-%d").to_string(),
+            stat.last_seen.format("%Y-%m-%d").to_string()
+        ));
+    }
+    
+    output.push_str("└────────────┴──────────┴──────────────┴───────────────┴──────────────┴──────────────┴───────────┴─────────────┴────────────┴────────────┘\n");
+    
+    // Calculate totals
+    let total_tokens: u64 = stats.iter().map(|s| s.total_tokens).sum();
+    let total_cost: f64 = stats.iter().map(|s| s.cost_usd).sum();
+    let long_context_cost = stats.iter().fold(0.0, |total, s| total + s.long_context_cost_usd);
+    
+    output.push_str(&format!("\nTotal Usage: {} tokens | Total Cost: ${:.2}", format_number(total_tokens), total_cost));
+    if let Some(note) = format_long_context_note(long_context_cost, total_cost) {
+        output.push_str(&format!("\n{}", note));
+    }
+    
+    output
+}
+
+/// Format main-thread vs sidechain usage, one row per day, month, session or project
+pub fn format_sidechain_table(label: &str, rows: impl IntoIterator<Item = (String, SidechainSplit)>) -> String {
+    let mut output = String::new();
+    let mut total = SidechainSplit::default();
+    
+    // Header
+    output.push_str("┌────────────────────────────────┬──────────────┬─────────────┬──────────────┬─────────────┬────────────┐\n");
+    output.push_str(&format!(
+        "│ {:<30} │  Main Tokens │   Main Cost │  Side Tokens │   Side Cost │ Side Share │\n",
+        label
+    ));
+    output.push_str("├────────────────────────────────┼──────────────┼─────────────┼──────────────┼─────────────┼────────────┤\n");
+    
+    // Data rows
+    for (name, split) in rows {
+        output.push_str(&format!(
+            "│ {:<30} │ {:>12} │ {:>11.2} │ {:>12} │ {:>11.2} │ {:>9.1}% │\n",
+            shorten_path(&name, 30),
+            format_number(split.main_tokens),
+            split.main_cost_usd,
+            format_number(split.sidechain_tokens),
+            split.sidechain_cost_usd,
+            split.sidechain_cost_share() * 100.0
+        ));
+        total.merge(&split);
+    }
+    
+    output.push_str("└────────────────────────────────┴──────────────┴─────────────┴──────────────┴─────────────┴────────────┘\n");
+    
+    output.push_str(&format!(
+        "\nMain thread: {} tokens | ${:.2}\nSidechains:  {} tokens | ${:.2} ({:.1}% of cost)",
+        format_number(total.main_tokens),
+        total.main_cost_usd,
+        format_number(total.sidechain_tokens),
+        total.sidechain_cost_usd,
+        total.sidechain_cost_share() * 100.0
+    ));
+    
+    output
+}
+
+/// Format per-session final path vs discarded branch costs
+pub fn format_branches_table(sessions: &[SessionBranches]) -> String {
+    let mut output = String::new();
+    
+    // Header
+    output.push_str("┌──────────────┬─────────────────────┬──────────┬───────────┬─────────────┬─────────────┬─────────────┬───────────┐\n");
+    output.push_str("│ Session ID   │ Start Time          │ Branches │ Abandoned │  Final Path │   Discarded │  Sidechains │ Discarded │\n");
+    output.push_str("│              │                     │          │           │  Cost (USD) │  Cost (USD) │  Cost (USD) │   Share   │\n");
+    output.push_str("├──────────────┼─────────────────────┼──────────┼───────────┼─────────────┼─────────────┼─────────────┼───────────┤\n");
+    
+    // Data rows
+    for session in sessions {
+        output.push_str(&format!(
+            "│ {:<12} │ {:<19} │ {:>8} │ {:>9} │ {:>11.2} │ {:>11.2} │ {:>11.2} │ {:>8.1}% │\n",
+            short_session_id(&session.session_id),
+            session.start.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string(),
+            session.branches.len(),
+            session.abandoned_count(),
+            session.final_cost(),
+            session.discarded_cost(),
+            session.sidechain_cost_usd,
+            share_percent(session.discarded_cost(), session.total_cost())
+        ));
+    }
+    
+    output.push_str("└──────────────┴─────────────────────┴──────────┴───────────┴─────────────┴─────────────┴─────────────┴───────────┘\n");
+    
+    // Calculate totals
+    let final_cost: f64 = sessions.iter().map(|s| s.final_cost()).sum();
+    let discarded_cost: f64 = sessions.iter().map(|s| s.discarded_cost()).sum();
+    let total_cost: f64 = sessions.iter().map(|s| s.total_cost()).sum();
+    
+    output.push_str(&format!(
+        "\nFinal path: ${:.2} | Discarded exploration: ${:.2} ({:.1}% of ${:.2})",
+        final_cost,
+        discarded_cost,
+        share_percent(discarded_cost, total_cost),
+        total_cost
+    ));
+    
+    output
+}
+
+/// Format the individual branches of one session
+pub fn format_branch_list(session: &SessionBranches) -> String {
+    let mut output = String::new();
+    
+    output.push_str(&format!("Session {} ({})\n", session.session_id, session.project));
+    output.push_str("┌──────┬──────────────┬──────────────┬─────────────────────┬──────────┬──────────────┬─────────────┬───────────┐\n");
+    output.push_str("│ #    │ First Msg    │ Forked From  │ Start Time          │ Messages │       Tokens │ Cost (USD)  │ Status    │\n");
+    output.push_str("├──────┼──────────────┼──────────────┼─────────────────────┼──────────┼──────────────┼─────────────┼───────────┤\n");
+    
+    for (index, branch) in session.branches.iter().enumerate() {
+        output.push_str(&format!(
+            "│ {:<4} │ {:<12} │ {:<12} │ {:<19} │ {:>8} │ {:>12} │ {:>11.2} │ {:<9} │\n",
+            index + 1,
+            short_session_id(&branch.first_uuid),
+            branch.forked_from.as_deref().map_or("-".to_string(), short_session_id),
+            branch.start.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string(),
+            branch.messages,
+            format_number(branch.tokens),
+            branch.cost_usd,
+            if branch.is_final { "final" } else { "abandoned" }
+        ));
+    }
+    
+    output.push_str("└──────┴──────────────┴──────────────┴─────────────────────┴──────────┴──────────────┴─────────────┴───────────┘\n");
+    
+    output.push_str(&format!(
+        "Final path: ${:.2} | Discarded: ${:.2} | Sidechains: ${:.2}\n",
+        session.final_cost(),
+        session.discarded_cost(),
+        session.sidechain_cost_usd
+    ));
+    
+    output
+}
+
+/// Format per-tool usage, with each tool's share of the cost of all turns
+pub fn format_tools_table(stats: &[ToolStats], total_cost: f64, by_server: bool) -> String {
+    let mut output = String::new();
+    
+    // Header
+    output.push_str("┌────────────────────────────────────────┬──────────┬──────────┬──────────┬───────────────┬─────────────┬────────────┐\n");
+    output.push_str(&format!(
+        "│ {:<38} │    Calls │   Errors │    Turns │ Output Tokens │ Cost (USD)  │ Cost Share │\n",
+        if by_server { "Tool / MCP Server" } else { "Tool" }
+    ));
+    output.push_str("├────────────────────────────────────────┼──────────┼──────────┼──────────┼───────────────┼─────────────┼────────────┤\n");
+    
+    // Data rows
+    for stat in stats {
+        output.push_str(&format!(
+            "│ {:<38} │ {:>8} │ {:>8} │ {:>8} │ {:>13} │ {:>11.2} │ {:>9.1}% │\n",
+            shorten_path(&stat.tool, 38),
+            format_number(stat.calls as u64),
+            format_number(stat.errors as u64),
+            format_number(stat.turns as u64),
+            format_number(stat.output_tokens),
+            stat.cost_usd,
+            share_percent(stat.cost_usd, total_cost)
+        ));
+    }
+    
+    output.push_str("└────────────────────────────────────────┴──────────┴──────────┴──────────┴───────────────┴─────────────┴────────────┘\n");
+    
+    // Calculate totals
+    let total_calls: usize = stats.iter().map(|s| s.calls).sum();
+    let attributed_cost = stats.iter().fold(0.0, |total, s| total + s.cost_usd);
+    
+    output.push_str(&format!(
+        "\nTool calls: {} | Attributed cost: ${:.2} ({:.1}% of ${:.2} across all turns)",
+        format_number(total_calls as u64),
+        attributed_cost,
+        share_percent(attributed_cost, total_cost),
+        total_cost
+    ));
     
     output
-}
\ No newline at end of file
+}
+
+/// Cost cell text, marked `*` when it is not based on known rates
+fn format_cost(cost: f64, source: PricingSource) -> String {
+    format!("{:.2}{}", cost, source.marker())
+}
+
+/// Pricing source of a session over all of its models
+fn session_pricing_source(session: &crate::jsonl_parser::SessionData) -> PricingSource {
+    session
+        .model_usage
+        .values()
+        .fold(PricingSource::Known, |source, usage| source.combine(usage.pricing_source))
+}
+
+fn share_percent(part: f64, total: f64) -> f64 {
+    if total > 0.0 { part / total * 100.0 } else { 0.0 }
+}
+
+/// Number of line-level issues listed per file in the doctor report
+const DOCTOR_ISSUES_PER_FILE: usize = 5;
+
+pub fn format_doctor_report(report: &DoctorReport) -> String {
+    let mut output = String::new();
+    
+    if report.inputs.is_empty() {
+        output.push_str("Data roots:\n");
+        if report.roots.is_empty() {
+            output.push_str("  (no existing Claude directories)\n");
+        }
+        for root in &report.roots {
+            output.push_str(&format!("  {}\n", root.display()));
+        }
+        output.push_str(&format!(
+            "Projects: {} | Session files: {}\n\n",
+            report.project_count,
+            report.files.len()
+        ));
+    } else {
+        output.push_str("Inputs:\n");
+        for input in &report.inputs {
+            output.push_str(&format!("  {}\n", input));
+        }
+        output.push_str(&format!("Session files: {}\n\n", report.files.len()));
+    }
+    
+    let files_with_issues: Vec<_> = report.files.iter().filter(|file| file.has_issues()).collect();
+    
+    if files_with_issues.is_empty() {
+        output.push_str("No problems found in session files.\n");
+    } else {
+        output.push_str("┌────────────────────────────────────────┬──────────┬──────────┬──────────┬──────────┬────────────┬──────────┐\n");
+        output.push_str("│ File                                   │    Lines │    Usage │  Skipped │    No ID │ Duplicates │ Bad Time │\n");
+        output.push_str("├────────────────────────────────────────┼──────────┼──────────┼──────────┼──────────┼────────────┼──────────┤\n");
+        
+        for file in &files_with_issues {
+            output.push_str(&format!(
+                "│ {:<38} │ {:>8} │ {:>8} │ {:>8} │ {:>8} │ {:>10} │ {:>8} │\n",
+                shorten_path(&file.path.display().to_string(), 38),
+                format_number(file.lines_read as u64),
+                format_number(file.usage_entries as u64),
+                file.unparseable.len(),
+                file.missing_ids.len(),
+                file.duplicates_dropped,
+                file.bad_timestamps.len()
+            ));
+        }
+        
+        output.push_str("└────────────────────────────────────────┴──────────┴──────────┴──────────┴──────────┴────────────┴──────────┘\n");
+        
+        // Line-level details for the problems that lose data
+        for file in &files_with_issues {
+            if file.read_error.is_none() && file.unparseable.is_empty() && file.bad_timestamps.is_empty() {
+                continue;
+            }
+            
+            output.push_str(&format!("\n{}\n", file.path.display()));
+            if let Some(error) = &file.read_error {
+                output.push_str(&format!("  read error: {}\n", error));
+            }
+            for issue in file.unparseable.iter().take(DOCTOR_ISSUES_PER_FILE) {
+                output.push_str(&format!("  line {}: unparseable: {}\n", issue.line, issue.message));
+            }
+            for issue in file.bad_timestamps.iter().take(DOCTOR_ISSUES_PER_FILE) {
+                output.push_str(&format!("  line {}: bad timestamp {}\n", issue.line, issue.message));
+            }
+            let hidden = file.unparseable.len().saturating_sub(DOCTOR_ISSUES_PER_FILE)
+                + file.bad_timestamps.len().saturating_sub(DOCTOR_ISSUES_PER_FILE);
+            if hidden > 0 {
+                output.push_str(&format!("  ... and {} more (use --json for all)\n", hidden));
+            }
+        }
+    }
+    
+    if !report.unknown_models.is_empty() {
+        output.push_str("\nUnknown models priced with fallback rates:\n");
+        for (model, count) in &report.unknown_models {
+            output.push_str(&format!("  {} ({} entries)\n", model, format_number(*count as u64)));
+        }
+    }
+    
+    output.push_str(&format!(
+        "\nLines: {} | Usage entries: {} | Skipped: {} | Without ID: {} | Duplicates: {} | Bad timestamps: {}",
+        format_number(report.total_lines() as u64),
+        format_number(report.total_usage_entries() as u64),
+        format_number(report.total_unparseable() as u64),
+        format_number(report.total_missing_ids() as u64),
+        format_number(report.total_duplicates() as u64),
+        format_number(report.total_bad_timestamps() as u64)
+    ));
+    
+    output
+}
+
+/// First 12 characters of a session or message id, which are enough to tell them apart
+fn short_session_id(id: &str) -> String {
+    if id.chars().count() > 12 {
+        format!("{}...", id.chars().take(12).collect::<String>())
+    } else {
+        id.to_string()
+    }
+}
+
+/// Keep the end of a long path, which carries the project name
+fn shorten_path(path: &str, width: usize) -> String {
+    let chars: Vec<char> = path.chars().collect();
+    if chars.len() <= width {
+        path.to_string()
+    } else {
+        let tail: String = chars[chars.len() - (width - 3)..].iter().collect();
+        format!("...{}", tail)
+    }
+}
@@ -0,0 +1,410 @@
+//! # Conversation Tree Module
+//!
+//! Rebuilds the message tree of each session from `uuid`/`parentUuid` links, so
+//! the cost of edits and rewinds (which fork the conversation) can be separated
+//! from the path that led to the final state
+//!
+//! ## Key Components
+//! - [`load_session_branches`] - Read session files into per-session branch summaries
+//! - [`SessionBranches`] - Branches of one session with final vs discarded cost
+//! - [`Branch`] - A chain of messages from a fork point (or root) to a leaf
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::data_sources::DataSourceOptions;
+use crate::entry_processor::{for_each_record, total_tokens};
+use crate::pricing::{check_unknown_models, entry_cost};
+
+/// One message of a session
+#[derive(Debug, Clone)]
+struct TreeNode {
+    uuid: String,
+    parent: Option<String>,
+    timestamp: DateTime<Utc>,
+    /// Sidechain messages link the tree together but their usage is reported separately
+    is_sidechain: bool,
+    tokens: u64,
+    cost_usd: f64,
+}
+
+/// A chain of messages from a fork point (or a root) down to a leaf
+#[derive(Debug, Clone, Serialize)]
+pub struct Branch {
+    /// First message of the branch
+    pub first_uuid: String,
+    /// Message the branch forked from, `None` for a root
+    pub forked_from: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub messages: usize,
+    pub tokens: u64,
+    pub cost_usd: f64,
+    /// Whether the branch leads to the session's final state
+    pub is_final: bool,
+}
+
+/// The branches of one session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionBranches {
+    pub session_id: String,
+    pub project: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Final branch first, then abandoned branches in order of their start
+    pub branches: Vec<Branch>,
+    /// Usage of sidechains (Task subagents), which is not attributed to branches
+    pub sidechain_tokens: u64,
+    pub sidechain_cost_usd: f64,
+}
+
+impl SessionBranches {
+    pub fn final_cost(&self) -> f64 {
+        self.cost_of(|branch| branch.is_final)
+    }
+
+    pub fn discarded_cost(&self) -> f64 {
+        self.cost_of(|branch| !branch.is_final)
+    }
+
+    pub fn abandoned_count(&self) -> usize {
+        self.branches.iter().filter(|branch| !branch.is_final).count()
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.final_cost() + self.discarded_cost() + self.sidechain_cost_usd
+    }
+
+    // Folded from 0.0 so an empty selection is not reported as -0.00
+    fn cost_of(&self, select: impl Fn(&Branch) -> bool) -> f64 {
+        self.branches.iter().filter(|branch| select(branch)).fold(0.0, |total, branch| total + branch.cost_usd)
+    }
+}
+
+/// Messages collected for one session before the tree is built
+#[derive(Debug, Default)]
+struct SessionNodes {
+    project: String,
+    nodes: Vec<TreeNode>,
+    /// Main-thread usage entries without a `uuid`, which cannot be placed in the tree
+    unlinked: Vec<TreeNode>,
+    seen_uuids: HashSet<String>,
+    sidechain_tokens: u64,
+    sidechain_cost_usd: f64,
+}
+
+/// Read every session's messages and split each conversation into branches
+///
+/// Messages come from the same records as the usage reports, so each session's
+/// costs add up to its total in the `session` report; a message id repeated in a
+/// resumed file is counted once. Usage entries without a `uuid` cannot be placed
+/// in the tree and are attributed to the final branch.
+pub fn load_session_branches(session_files: &[PathBuf], sources: &DataSourceOptions) -> Result<Vec<SessionBranches>> {
+    let mut sessions: Vec<(String, SessionNodes)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for_each_record(session_files, sources, |_| {}, |record, counted| {
+        if record.session_id.is_empty() || (record.uuid.is_empty() && !counted) {
+            return;
+        }
+
+        let (tokens, cost_usd) = if counted {
+            let (cost, _) = entry_cost(&record.usage, &record.model, record.cost_usd, record.timestamp);
+            (total_tokens(&record.usage), cost)
+        } else {
+            (0, 0.0)
+        };
+
+        let position = *positions.entry(record.session_id.clone()).or_insert_with(|| {
+            sessions.push((
+                record.session_id.clone(),
+                SessionNodes {
+                    project: record.project.clone(),
+                    ..Default::default()
+                },
+            ));
+            sessions.len() - 1
+        });
+        let session = &mut sessions[position].1;
+
+        if record.is_sidechain {
+            session.sidechain_tokens += tokens;
+            session.sidechain_cost_usd += cost_usd;
+        }
+        let node = TreeNode {
+            uuid: record.uuid.clone(),
+            parent: record.parent_uuid.clone(),
+            timestamp: record.timestamp,
+            is_sidechain: record.is_sidechain,
+            tokens,
+            cost_usd,
+        };
+        if record.uuid.is_empty() {
+            if !record.is_sidechain {
+                session.unlinked.push(node);
+            }
+        } else if session.seen_uuids.insert(record.uuid.clone()) {
+            session.nodes.push(node);
+        }
+    })?;
+
+    check_unknown_models()?;
+    Ok(sessions
+        .into_iter()
+        .filter_map(|(session_id, session)| split_into_branches(session_id, session))
+        .collect())
+}
+
+/// Decompose a session's message tree into branches
+///
+/// At every fork the child leading to the most recent leaf continues the current
+/// branch and each other child starts a new one, so the branch starting at the
+/// root with the most recent leaf is exactly the path to the final state. Only
+/// main-thread messages count; trees made of sidechain messages alone are dropped.
+/// Messages without a `uuid` join the final branch, or form it when there is no tree.
+fn split_into_branches(session_id: String, session: SessionNodes) -> Option<SessionBranches> {
+    let nodes = session.nodes;
+    let index: HashMap<&str, usize> = nodes.iter().enumerate().map(|(i, node)| (node.uuid.as_str(), i)).collect();
+
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    let mut roots = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        match node.parent.as_deref().and_then(|parent| index.get(parent)) {
+            Some(&parent) if parent != i => children[parent].push(i),
+            // Messages whose parent is missing from the logs start their own tree
+            _ => roots.push(i),
+        }
+    }
+
+    // Pre-order walk, iterative because conversations can be thousands of messages deep
+    let mut order = Vec::with_capacity(nodes.len());
+    let mut visited = vec![false; nodes.len()];
+    let mut stack: Vec<usize> = roots.iter().rev().copied().collect();
+    while let Some(i) = stack.pop() {
+        if std::mem::replace(&mut visited[i], true) {
+            continue;
+        }
+        order.push(i);
+        stack.extend(children[i].iter().rev());
+    }
+
+    // Most recent main-thread timestamp in each subtree, children before parents
+    let mut latest: Vec<Option<DateTime<Utc>>> =
+        nodes.iter().map(|node| (!node.is_sidechain).then_some(node.timestamp)).collect();
+    for &i in order.iter().rev() {
+        if let Some(max_child) = children[i].iter().map(|&child| latest[child]).max() {
+            latest[i] = latest[i].max(max_child);
+        }
+    }
+
+    let final_root = roots.iter().copied().max_by_key(|&root| (latest[root], root));
+
+    let mut branches: Vec<Branch> = Vec::new();
+    let mut pending: Vec<(usize, Option<String>)> = roots.iter().map(|&root| (root, None)).collect();
+    while let Some((first, forked_from)) = pending.pop() {
+        if latest[first].is_none() {
+            continue;
+        }
+
+        let mut branch = Branch {
+            first_uuid: nodes[first].uuid.clone(),
+            forked_from,
+            start: nodes[first].timestamp,
+            end: nodes[first].timestamp,
+            messages: 0,
+            tokens: 0,
+            cost_usd: 0.0,
+            is_final: Some(first) == final_root,
+        };
+
+        let mut current = Some(first);
+        while let Some(i) = current {
+            let node = &nodes[i];
+            branch.start = branch.start.min(node.timestamp);
+            branch.end = branch.end.max(node.timestamp);
+            if !node.is_sidechain {
+                branch.messages += 1;
+                branch.tokens += node.tokens;
+                branch.cost_usd += node.cost_usd;
+            }
+
+            let heir = children[i].iter().copied().max_by_key(|&child| (latest[child], child));
+            for &child in children[i].iter().filter(|&&child| Some(child) != heir) {
+                pending.push((child, Some(node.uuid.clone())));
+            }
+            current = heir;
+        }
+
+        branches.push(branch);
+    }
+
+    if let Some(first) = session.unlinked.first() {
+        if !branches.iter().any(|branch| branch.is_final) {
+            branches.push(Branch {
+                first_uuid: String::new(),
+                forked_from: None,
+                start: first.timestamp,
+                end: first.timestamp,
+                messages: 0,
+                tokens: 0,
+                cost_usd: 0.0,
+                is_final: true,
+            });
+        }
+        if let Some(branch) = branches.iter_mut().find(|branch| branch.is_final) {
+            for node in &session.unlinked {
+                branch.start = branch.start.min(node.timestamp);
+                branch.end = branch.end.max(node.timestamp);
+                branch.messages += 1;
+                branch.tokens += node.tokens;
+                branch.cost_usd += node.cost_usd;
+            }
+        }
+    }
+
+    if branches.is_empty() {
+        return None;
+    }
+    branches.sort_by_key(|branch| (!branch.is_final, branch.start));
+
+    let timestamps = || nodes.iter().chain(&session.unlinked).map(|node| node.timestamp);
+    Some(SessionBranches {
+        session_id,
+        project: session.project,
+        start: timestamps().min().unwrap_or_default(),
+        end: timestamps().max().unwrap_or_default(),
+        branches,
+        sidechain_tokens: session.sidechain_tokens,
+        sidechain_cost_usd: session.sidechain_cost_usd,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn message(uuid: &str, parent: Option<&str>, minute: u32, cost: f64, sidechain: bool) -> String {
+        format!(
+            r#"{{"sessionId":"s1","uuid":"{}","parentUuid":{},"isSidechain":{},"timestamp":"2025-06-01T10:{:02}:00Z","requestId":"r-{}","message":{{"id":"m-{}","model":"claude-sonnet-4-20250514","usage":{{"input_tokens":10}},"costUSD":{}}}}}"#,
+            uuid,
+            parent.map_or("null".to_string(), |parent| format!("\"{}\"", parent)),
+            sidechain,
+            minute,
+            uuid,
+            uuid,
+            cost
+        )
+    }
+
+    #[test]
+    fn test_rewinds_split_final_path_from_discarded_exploration() {
+        let dir = TempDir::new("tree");
+        let project = dir.join("-home-me-repo");
+        std::fs::create_dir_all(&project).unwrap();
+        let file = project.join("session.jsonl");
+
+        // a -> b -> c is abandoned by rewinding to b (d), then to a again (f)
+        std::fs::write(
+            &file,
+            [
+                message("a", None, 0, 1.0, false),
+                message("b", Some("a"), 1, 2.0, false),
+                message("c", Some("b"), 2, 4.0, false),
+                message("d", Some("b"), 3, 8.0, false),
+                message("e", Some("d"), 4, 16.0, false),
+                message("f", Some("a"), 5, 32.0, false),
+                message("task", None, 6, 64.0, true),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let sessions = load_session_branches(std::slice::from_ref(&file), &DataSourceOptions::default()).unwrap();
+        let session = &sessions[0];
+
+        assert_eq!(session.branches.len(), 3);
+        let final_branch = &session.branches[0];
+        assert!(final_branch.is_final);
+        assert_eq!((final_branch.first_uuid.as_str(), final_branch.messages), ("a", 2));
+        assert_eq!(session.final_cost(), 33.0);
+
+        // b -> d -> e forked from a; c forked from b
+        assert_eq!(session.abandoned_count(), 2);
+        assert_eq!(session.branches[1].forked_from.as_deref(), Some("a"));
+        assert_eq!(session.branches[1].cost_usd, 26.0);
+        assert_eq!(session.branches[2].forked_from.as_deref(), Some("b"));
+        assert_eq!(session.discarded_cost(), 30.0);
+
+        assert_eq!(session.sidechain_cost_usd, 64.0);
+        assert_eq!(session.total_cost(), 127.0);
+    }
+
+    #[test]
+    fn test_tree_uses_shared_records_across_files() {
+        let dir = TempDir::new("tree-shared");
+        let project = dir.join("-home-me-repo");
+        std::fs::create_dir_all(&project).unwrap();
+        let first = project.join("first.jsonl");
+        let resumed = project.join("resumed.jsonl");
+
+        // A user message without usage links "b" to "a"
+        let user = r#"{"sessionId":"s1","uuid":"u","parentUuid":"a","timestamp":"2025-06-01T10:01:00Z","type":"user","message":{"role":"user","content":"go on"}}"#;
+        std::fs::write(&first, [message("a", None, 0, 1.0, false), user.to_string()].join("\n")).unwrap();
+        // The resumed file repeats "a" and adds a malformed line
+        std::fs::write(
+            &resumed,
+            [message("a", None, 0, 1.0, false), message("b", Some("u"), 2, 2.0, false), r#"{"sessionId":"s1","uu"#.to_string()].join("\n"),
+        )
+        .unwrap();
+        let files = [first, resumed];
+
+        let sessions = load_session_branches(&files, &DataSourceOptions::default()).unwrap();
+        assert_eq!(sessions[0].branches.len(), 1);
+        assert_eq!(sessions[0].branches[0].messages, 3);
+        assert_eq!(sessions[0].total_cost(), 3.0);
+
+        let strict = DataSourceOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(load_session_branches(&files, &strict).is_err());
+    }
+
+    #[test]
+    fn test_usage_without_uuid_joins_final_branch() {
+        let dir = TempDir::new("tree-unlinked");
+        let project = dir.join("-home-me-repo");
+        std::fs::create_dir_all(&project).unwrap();
+        let file = project.join("session.jsonl");
+
+        // "s1" rewinds from b to c and logs usage without a uuid; "s2" has no uuids at all
+        let unlinked = |minute, cost| message("", None, minute, cost, false).replace("r-", &format!("r-{}", minute));
+        std::fs::write(
+            &file,
+            [
+                message("a", None, 0, 1.0, false),
+                message("b", Some("a"), 1, 2.0, false),
+                message("c", Some("a"), 2, 4.0, false),
+                unlinked(3, 8.0),
+                unlinked(4, 16.0).replace(r#""s1""#, r#""s2""#),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let sessions = load_session_branches(std::slice::from_ref(&file), &DataSourceOptions::default()).unwrap();
+        let final_branch = &sessions[0].branches[0];
+        assert_eq!((final_branch.first_uuid.as_str(), final_branch.messages), ("a", 3));
+        assert_eq!(sessions[0].final_cost(), 13.0);
+        assert_eq!(sessions[0].discarded_cost(), 2.0);
+
+        let reported = crate::entry_processor::load_sessions(std::slice::from_ref(&file), &DataSourceOptions::default()).unwrap();
+        assert_eq!(sessions.len(), reported.len());
+        assert_eq!(sessions[1].branches.len(), 1);
+        assert_eq!(sessions[1].total_cost(), 16.0);
+    }
+}
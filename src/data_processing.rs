@@ -8,16 +8,18 @@
 //! - [`sort_daily_stats`] - Sort daily statistics by date
 //! - [`MonthlyStats`] - Monthly aggregated statistics
 //! - [`SessionStats`] - Session-level statistics
+//! - [`ProjectStats`] - Per-project aggregated statistics
+//! - [`VersionStats`] - Per-Claude Code version aggregated statistics
 
 use anyhow::{Context, Result};
-use chrono::NaiveDate;
+use chrono::{DateTime, Local, NaiveDate};
 use serde::Serialize;
-use std::collections::HashMap;
 
 use crate::cli::SortOrder;
-use crate::table_display::DailyStats;
+use crate::entry_processor::ProcessedEntry;
+use crate::table_display::{DailyStats, SidechainSplit};
 use crate::jsonl_parser::SessionData;
-use crate::pricing::calculate_session_cost;
+use crate::pricing::{PricingSource, calculate_session_cost};
 
 #[derive(Debug, Serialize)]
 pub struct MonthlyStats {
@@ -26,12 +28,21 @@ pub struct MonthlyStats {
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub cache_creation_tokens: u64,
+    /// Part of `cache_creation_tokens` written with a 1-hour cache lifetime
+    pub cache_creation_1h_tokens: u64,
     pub cache_read_tokens: u64,
     pub total_tokens: u64,
     pub cost_usd: f64,
+    pub sidechain: SidechainSplit,
+    pub pricing_source: PricingSource,
+    /// Cost of requests billed at long-context (premium tier) rates
+    pub long_context_cost_usd: f64,
+    /// Requests billed at long-context rates
+    pub long_context_requests: usize,
 }
 
 #[derive(Debug, Serialize)]
+#[allow(dead_code)]
 pub struct SessionStats {
     pub session_id: String,
     pub start_time: String,
@@ -40,6 +51,55 @@ pub struct SessionStats {
     pub cost_usd: f64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ProjectStats {
+    pub project: String,
+    pub path: String,
+    pub models: Vec<String>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    pub session_count: usize,
+    pub sidechain: SidechainSplit,
+    pub pricing_source: PricingSource,
+    /// Cost of requests billed at long-context (premium tier) rates
+    pub long_context_cost_usd: f64,
+    pub first_activity: DateTime<Local>,
+    pub last_activity: DateTime<Local>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionStats {
+    pub version: String,
+    pub entry_count: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    pub pricing_source: PricingSource,
+    /// Cost of requests billed at long-context (premium tier) rates
+    pub long_context_cost_usd: f64,
+    pub first_seen: DateTime<Local>,
+    pub last_seen: DateTime<Local>,
+}
+
+impl VersionStats {
+    /// Share of prompt tokens served from the cache
+    pub fn cache_hit_ratio(&self) -> f64 {
+        let prompt_tokens = self.input_tokens + self.cache_creation_tokens + self.cache_read_tokens;
+        if prompt_tokens == 0 {
+            0.0
+        } else {
+            self.cache_read_tokens as f64 / prompt_tokens as f64
+        }
+    }
+}
+
 /// Parse date in YYYYMMDD format
 pub fn parse_date_filter(date_str: &str) -> Result<NaiveDate> {
     if date_str.len() != 8 {
@@ -91,6 +151,28 @@ pub fn filter_daily_stats_by_date(
     Ok(filtered)
 }
 
+/// Filter processed entries by date range
+pub fn filter_entries_by_date(
+    entries: Vec<ProcessedEntry>,
+    since: Option<&str>,
+    until: Option<&str>,
+) -> Result<Vec<ProcessedEntry>> {
+    let since_date = since.map(parse_date_filter).transpose()?;
+    let until_date = until.map(parse_date_filter).transpose()?;
+    
+    let filtered: Vec<ProcessedEntry> = entries
+        .into_iter()
+        .filter(|entry| {
+            let date = entry.timestamp.date_naive();
+            let after_since = since_date.is_none_or(|since| date >= since);
+            let before_until = until_date.is_none_or(|until| date <= until);
+            after_since && before_until
+        })
+        .collect();
+    
+    Ok(filtered)
+}
+
 /// Sort daily statistics by date
 pub fn sort_daily_stats(mut daily_stats: Vec<DailyStats>, order: SortOrder) -> Vec<DailyStats> {
     daily_stats.sort_by(|a, b| {
@@ -102,48 +184,6 @@ pub fn sort_daily_stats(mut daily_stats: Vec<DailyStats>, order: SortOrder) -> V
     daily_stats
 }
 
-/// Aggregate daily statistics into monthly summaries
-pub fn aggregate_monthly_stats(daily_stats: &[DailyStats]) -> Result<Vec<MonthlyStats>> {
-    let mut monthly_map: HashMap<String, MonthlyStats> = HashMap::new();
-    
-    for daily_stat in daily_stats {
-        // Extract year-month from date (YYYY-MM-DD -> YYYY-MM)
-        let month_key = daily_stat.date.chars().take(7).collect::<String>();
-        
-        let monthly_stat = monthly_map.entry(month_key.clone()).or_insert_with(|| MonthlyStats {
-            month: month_key,
-            models: Vec::new(),
-            input_tokens: 0,
-            output_tokens: 0,
-            cache_creation_tokens: 0,
-            cache_read_tokens: 0,
-            total_tokens: 0,
-            cost_usd: 0.0,
-        });
-        
-        // Aggregate models (ensure uniqueness)
-        for model in &daily_stat.models {
-            if !monthly_stat.models.contains(model) {
-                monthly_stat.models.push(model.clone());
-            }
-        }
-        
-        // Aggregate token counts
-        monthly_stat.input_tokens += daily_stat.input_tokens;
-        monthly_stat.output_tokens += daily_stat.output_tokens;
-        monthly_stat.cache_creation_tokens += daily_stat.cache_creation_tokens;
-        monthly_stat.cache_read_tokens += daily_stat.cache_read_tokens;
-        monthly_stat.total_tokens += daily_stat.total_tokens;
-        monthly_stat.cost_usd += daily_stat.cost_usd;
-    }
-    
-    // Convert to sorted vector
-    let mut monthly_stats: Vec<MonthlyStats> = monthly_map.into_values().collect();
-    monthly_stats.sort_by(|a, b| a.month.cmp(&b.month));
-    
-    Ok(monthly_stats)
-}
-
 /// Sort monthly statistics
 pub fn sort_monthly_stats(mut stats: Vec<MonthlyStats>, order: SortOrder) -> Vec<MonthlyStats> {
     stats.sort_by(|a, b| {
@@ -155,6 +195,17 @@ pub fn sort_monthly_stats(mut stats: Vec<MonthlyStats>, order: SortOrder) -> Vec
     stats
 }
 
+/// Sort project statistics by cost
+pub fn sort_project_stats(mut stats: Vec<ProjectStats>, order: SortOrder) -> Vec<ProjectStats> {
+    stats.sort_by(|a, b| {
+        match order {
+            SortOrder::Asc => a.cost_usd.partial_cmp(&b.cost_usd).unwrap_or(std::cmp::Ordering::Equal),
+            SortOrder::Desc => b.cost_usd.partial_cmp(&a.cost_usd).unwrap_or(std::cmp::Ordering::Equal),
+        }
+    });
+    stats
+}
+
 /// Filter sessions by date range
 pub fn filter_sessions_by_date(
     sessions: Vec<SessionData>,
@@ -213,6 +264,7 @@ pub fn apply_recent_filter_daily(mut daily_stats: Vec<DailyStats>, recent_days:
 }
 
 /// Apply recent filtering to sessions (keep last N sessions)
+#[allow(clippy::unnecessary_sort_by)]
 pub fn apply_recent_filter_sessions(mut sessions: Vec<SessionData>, recent_count: Option<usize>) -> Vec<SessionData> {
     if let Some(count) = recent_count {
         // Sort by start time descending to get most recent
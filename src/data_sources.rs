@@ -0,0 +1,662 @@
+//! # Data Sources Module
+//!
+//! Resolves where Claude Code session logs live and which files to read
+//!
+//! ## Key Components
+//! - [`DataSourceOptions`] - Data location settings shared by every command
+//! - [`resolve_claude_roots`] - Resolve all `projects` roots from flag, env and defaults
+//! - [`DataSourceOptions::session_files`] - Collect session files across every root
+//! - [`DataSourceOptions::input_files`] - Explicit `--input` files, directories, globs and stdin
+//! - [`ProjectFilter`] - Restrict reports to one project (`--project` / `--here`)
+//! - [`encode_project_path`] / [`decode_project_dir_name`] - Claude's dash-encoded project names
+
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::cli::Args;
+use crate::{jsonl_parser, session_log};
+
+/// Environment variable holding one or more comma-separated Claude config directories
+pub const CLAUDE_CONFIG_DIR_ENV: &str = "CLAUDE_CONFIG_DIR";
+
+/// Data source settings shared by every command
+#[derive(Debug, Clone, Default)]
+pub struct DataSourceOptions {
+    /// Comma-separated Claude directories from `--claude-dir`
+    pub claude_dir: Option<String>,
+    /// Optional restriction to matching project directories
+    pub project_filter: Option<ProjectFilter>,
+    /// Reuse parsed records from the on-disk cache
+    pub use_cache: bool,
+    /// Fail on malformed session lines instead of skipping them
+    pub strict: bool,
+    /// Explicit `--input` sources replacing root discovery (`-` is stdin)
+    pub inputs: Vec<String>,
+    /// Copy of stdin when `-` is among the inputs
+    pub stdin_spool: Option<Arc<StdinSpool>>,
+    /// Project filter result per project directory name, since matching a name
+    /// decodes it against the filesystem
+    pub(crate) project_matches: Arc<Mutex<HashMap<String, bool>>>,
+}
+
+/// Input name that reads session data from standard input
+pub const STDIN_INPUT: &str = "-";
+
+/// Stdin saved to a temporary file so it can be read like any other session file
+///
+/// The file lives in a directory named `stdin`, which becomes its project name.
+/// It is removed when the last copy of the options is dropped.
+#[derive(Debug)]
+pub struct StdinSpool {
+    dir: PathBuf,
+    file: PathBuf,
+}
+
+impl StdinSpool {
+    fn read() -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!("ccusage-rs-stdin-{}", std::process::id()));
+        let project_dir = dir.join("stdin");
+        std::fs::create_dir_all(&project_dir).context("Failed to create directory for stdin")?;
+
+        let mut data = Vec::new();
+        std::io::stdin().read_to_end(&mut data).context("Failed to read stdin")?;
+
+        let file = project_dir.join("session.jsonl");
+        std::fs::write(&file, data).context("Failed to save stdin")?;
+        Ok(Self { dir, file })
+    }
+}
+
+impl Drop for StdinSpool {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Restricts discovery to the project directories of one project
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProjectFilter {
+    /// A project path; the most specific project containing it matches
+    Path(PathBuf),
+    /// A project name; matches the directory name or the last component of the decoded project path
+    Name(String),
+}
+
+impl ProjectFilter {
+    /// Build a filter from `--project <path|name>`
+    pub fn parse(value: &str) -> Result<Self> {
+        let looks_like_path = value == "."
+            || value == ".."
+            || value.starts_with('~')
+            || value.contains(std::path::MAIN_SEPARATOR)
+            || value.contains('/');
+
+        if looks_like_path {
+            let path = expand_home(value);
+            let absolute = if path.is_absolute() {
+                path
+            } else {
+                std::env::current_dir()
+                    .context("Failed to get current directory")?
+                    .join(path)
+            };
+            Ok(Self::Path(canonical_or_original(&absolute)))
+        } else {
+            Ok(Self::Name(value.to_string()))
+        }
+    }
+
+    /// Build a filter for `--here`
+    pub fn current_dir() -> Result<Self> {
+        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+        Ok(Self::Path(canonical_or_original(&cwd)))
+    }
+
+    /// Keep only the project directories matching this filter
+    pub fn apply(&self, project_dirs: Vec<PathBuf>) -> Vec<PathBuf> {
+        match self {
+            Self::Path(path) => {
+                // Pick the most specific project that contains the path, so running
+                // from a subdirectory of a repository still finds the repository
+                let ancestors: Vec<String> = path
+                    .ancestors()
+                    .filter(|ancestor| ancestor.parent().is_some())
+                    .map(encode_project_path)
+                    .collect();
+
+                let best = ancestors.iter().find(|encoded| {
+                    project_dirs.iter().any(|dir| dir_name(dir) == encoded.as_str())
+                });
+
+                match best {
+                    Some(encoded) => project_dirs
+                        .into_iter()
+                        .filter(|dir| dir_name(dir) == encoded.as_str())
+                        .collect(),
+                    None => Vec::new(),
+                }
+            }
+            Self::Name(name) => project_dirs
+                .into_iter()
+                .filter(|dir| {
+                    let dir_name = dir_name(dir);
+                    dir_name == name
+                        || decode_project_dir_name(dir_name)
+                            .file_name()
+                            .is_some_and(|last| last == name.as_str())
+                })
+                .collect(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Path(path) => path.display().to_string(),
+            Self::Name(name) => name.clone(),
+        }
+    }
+}
+
+impl DataSourceOptions {
+    pub fn from_args(args: &Args) -> Result<Self> {
+        let project_filter = if args.here {
+            Some(ProjectFilter::current_dir()?)
+        } else if let Some(project) = &args.project {
+            Some(ProjectFilter::parse(project)?)
+        } else {
+            None
+        };
+
+        let stdin_spool = if args.input.iter().any(|input| input == STDIN_INPUT) {
+            Some(Arc::new(StdinSpool::read()?))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            claude_dir: args.claude_dir.clone(),
+            project_filter,
+            use_cache: !args.no_cache,
+            strict: args.strict,
+            inputs: args.input.clone(),
+            stdin_spool,
+            project_matches: Arc::default(),
+        })
+    }
+
+    /// Temporary copy of stdin, which must never be cached across runs
+    pub fn stdin_file(&self) -> Option<&Path> {
+        self.stdin_spool.as_ref().map(|spool| spool.file.as_path())
+    }
+
+    /// Session files named by `--input`, in the order given
+    ///
+    /// Each input is stdin (`-`), a file (read whatever its extension), a
+    /// directory (searched recursively for session logs) or a glob pattern.
+    /// Files outside the project filter are skipped.
+    pub fn input_files(&self) -> Result<Vec<PathBuf>> {
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+
+        for input in &self.inputs {
+            let matched = if input == STDIN_INPUT {
+                let spool = self.stdin_spool.as_ref().context("Stdin was not read")?;
+                vec![spool.file.clone()]
+            } else {
+                let path = expand_home(input);
+                if path.is_file() {
+                    vec![path]
+                } else if path.is_dir() {
+                    session_logs_in(&path)
+                } else {
+                    let pattern = path.to_string_lossy().into_owned();
+                    let mut matched = Vec::new();
+                    for entry in glob::glob(&pattern).with_context(|| format!("Invalid input pattern '{}'", input))? {
+                        let entry = entry.context("Failed to read input")?;
+                        if entry.is_dir() {
+                            matched.extend(session_logs_in(&entry));
+                        } else {
+                            matched.push(entry);
+                        }
+                    }
+                    if matched.is_empty() {
+                        anyhow::bail!("Input '{}' does not match any file", input);
+                    }
+                    matched
+                }
+            };
+
+            for file in matched {
+                let project = file
+                    .parent()
+                    .and_then(|dir| dir.file_name())
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                // Archives mix projects and are filtered per record instead
+                if !session_log::is_archive(&file) && !self.includes_project(&project) {
+                    continue;
+                }
+                if seen.insert(canonical_or_original(&file)) {
+                    files.push(file);
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// All `projects` roots to read from, deduplicated
+    pub fn roots(&self) -> Vec<PathBuf> {
+        let env_value = std::env::var(CLAUDE_CONFIG_DIR_ENV).ok();
+        resolve_claude_roots(self.claude_dir.as_deref(), env_value.as_deref(), dirs::home_dir())
+    }
+
+    /// All project directories across every root, restricted by the project filter
+    pub fn project_dirs(&self) -> Vec<PathBuf> {
+        let project_dirs = jsonl_parser::get_all_project_dirs(&self.roots());
+        match &self.project_filter {
+            Some(filter) => filter.apply(project_dirs),
+            None => project_dirs,
+        }
+    }
+
+    /// Whether usage recorded for a project directory name passes the project filter
+    ///
+    /// Called for every archived record, so each project name is matched once.
+    pub fn includes_project(&self, project: &str) -> bool {
+        let Some(filter) = &self.project_filter else {
+            return true;
+        };
+        if let Some(&matches) = self.project_matches.lock().ok().as_ref().and_then(|memo| memo.get(project)) {
+            return matches;
+        }
+
+        let matches = !filter.apply(vec![PathBuf::from(project)]).is_empty();
+        if let Ok(mut memo) = self.project_matches.lock() {
+            memo.insert(project.to_string(), matches);
+        }
+        matches
+    }
+
+    /// Find all session files in the given project directories, plus archives stored in the roots
+    pub fn session_files(&self, project_dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let mut seen = HashSet::new();
+        let mut session_files = Vec::new();
+
+        let mut candidates = Vec::new();
+        for project_dir in project_dirs {
+            candidates.extend(
+                jsonl_parser::find_session_files(project_dir, None).context("Failed to find session files")?,
+            );
+        }
+        for root in self.roots() {
+            candidates.extend(root_archives(&root));
+        }
+
+        for file in candidates {
+            // The same file can be reachable through several roots (symlinks)
+            if seen.insert(canonical_or_original(&file)) {
+                session_files.push(file);
+            }
+        }
+
+        Ok(session_files)
+    }
+
+    /// Discover every session file, failing with a user-facing message if there is none
+    ///
+    /// Explicit `--input` sources replace discovery under the Claude roots.
+    pub fn discover_session_files(&self) -> Result<Vec<PathBuf>> {
+        if !self.inputs.is_empty() {
+            let files = self.input_files()?;
+            if files.is_empty() {
+                anyhow::bail!("No session files found in the given inputs: {}", self.inputs.join(", "));
+            }
+            return Ok(files);
+        }
+
+        let project_dirs = self.project_dirs();
+        let session_files = self.session_files(&project_dirs)?;
+
+        if project_dirs.is_empty() && session_files.is_empty() {
+            if let Some(filter) = &self.project_filter {
+                anyhow::bail!(
+                    "No Claude project matches '{}'. Looked in: {}",
+                    filter.describe(),
+                    format_roots(&self.roots())
+                );
+            }
+            anyhow::bail!(
+                "No Claude session data found. Looked in: {}. Use --claude-dir or {} to point at your Claude directory.",
+                format_roots(&self.roots()),
+                CLAUDE_CONFIG_DIR_ENV
+            );
+        }
+
+        if session_files.is_empty() {
+            anyhow::bail!(
+                "No JSONL session files found in project directories. This project may not have any Claude Code usage yet."
+            );
+        }
+
+        Ok(session_files)
+    }
+}
+
+/// Resolve Claude `projects` roots
+///
+/// Explicit locations (`--claude-dir` and `CLAUDE_CONFIG_DIR`, both comma-separated)
+/// are merged together. Without explicit locations both the XDG
+/// (`~/.config/claude/projects`) and legacy (`~/.claude/projects`) layouts are used.
+/// Only existing directories are returned, deduplicated by canonical path.
+pub fn resolve_claude_roots(
+    claude_dir: Option<&str>,
+    config_env: Option<&str>,
+    home: Option<PathBuf>,
+) -> Vec<PathBuf> {
+    let explicit: Vec<PathBuf> = [claude_dir, config_env]
+        .into_iter()
+        .flatten()
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(expand_home)
+        .collect();
+
+    let candidates = if explicit.is_empty() {
+        let home = home.unwrap_or_else(|| PathBuf::from("/"));
+        vec![home.join(".config").join("claude"), home.join(".claude")]
+    } else {
+        explicit
+    };
+
+    let mut seen = HashSet::new();
+    let mut roots = Vec::new();
+
+    for candidate in candidates {
+        let Some(root) = projects_root(&candidate) else {
+            continue;
+        };
+        if seen.insert(canonical_or_original(&root)) {
+            roots.push(root);
+        }
+    }
+
+    roots
+}
+
+/// Every session log below a directory, in path order
+fn session_logs_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&current) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+            } else if session_log::is_session_log(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Archives of project directories kept directly in a `projects` root
+fn root_archives(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+
+    let mut archives: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && session_log::is_archive(path))
+        .collect();
+    archives.sort();
+    archives
+}
+
+/// Accept either a Claude config directory or its `projects` directory
+fn projects_root(dir: &Path) -> Option<PathBuf> {
+    let projects = dir.join("projects");
+    if projects.is_dir() {
+        Some(projects)
+    } else if dir.is_dir() && dir.file_name().is_some_and(|name| name == "projects") {
+        Some(dir.to_path_buf())
+    } else {
+        None
+    }
+}
+
+/// Encode a path the way Claude Code names its project directories
+///
+/// Every character other than ASCII letters and digits becomes a dash, so
+/// `/home/me/my.repo` is stored as `-home-me-my-repo`.
+pub fn encode_project_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Decode a dash-encoded project directory name back into a path
+///
+/// The encoding is lossy (`-` may have been `/`, `.`, `_` or a literal dash), so
+/// the filesystem is consulted to find the existing path the name encodes. When
+/// nothing on disk matches, every dash is treated as a path separator.
+pub fn decode_project_dir_name(name: &str) -> PathBuf {
+    let parts: Vec<&str> = name.trim_start_matches('-').split('-').collect();
+
+    if let Some(found) = resolve_encoded_parts(PathBuf::from("/"), &parts) {
+        return found;
+    }
+
+    let mut naive = PathBuf::from("/");
+    for part in parts.iter().filter(|part| !part.is_empty()) {
+        naive.push(part);
+    }
+    naive
+}
+
+fn resolve_encoded_parts(base: PathBuf, parts: &[&str]) -> Option<PathBuf> {
+    if parts.is_empty() {
+        return Some(base);
+    }
+
+    let Ok(entries) = std::fs::read_dir(&base) else {
+        return None;
+    };
+    let entries: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .map(|entry| (encode_project_path(Path::new(&entry.file_name())), entry.path()))
+        .collect();
+
+    // Try the longest component first; literal dashes, dots and underscores
+    // inside a component all encode to '-'
+    for len in (1..=parts.len()).rev() {
+        let encoded = parts[..len].join("-");
+        for (candidate, path) in &entries {
+            if *candidate == encoded
+                && path.is_dir()
+                && let Some(found) = resolve_encoded_parts(path.clone(), &parts[len..])
+            {
+                return Some(found);
+            }
+        }
+    }
+
+    None
+}
+
+fn dir_name(dir: &Path) -> &str {
+    dir.file_name().and_then(|name| name.to_str()).unwrap_or_default()
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(home) = dirs::home_dir() {
+        return home.join(rest);
+    }
+    PathBuf::from(path)
+}
+
+fn canonical_or_original(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Format roots for error messages
+pub fn format_roots(roots: &[PathBuf]) -> String {
+    if roots.is_empty() {
+        "(no existing Claude directories)".to_string()
+    } else {
+        roots
+            .iter()
+            .map(|root| root.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_default_roots_include_both_layouts() {
+        let home = TempDir::new("default-roots");
+        std::fs::create_dir_all(home.join(".claude/projects")).unwrap();
+        std::fs::create_dir_all(home.join(".config/claude/projects")).unwrap();
+
+        let roots = resolve_claude_roots(None, None, Some(home.path().to_path_buf()));
+
+        assert_eq!(roots, vec![
+            home.join(".config/claude/projects"),
+            home.join(".claude/projects"),
+        ]);
+    }
+
+    #[test]
+    fn test_explicit_roots_are_merged_and_deduplicated() {
+        let home = TempDir::new("explicit-roots");
+        let first = home.join("first");
+        let second = home.join("second");
+        std::fs::create_dir_all(first.join("projects")).unwrap();
+        std::fs::create_dir_all(second.join("projects")).unwrap();
+        std::fs::create_dir_all(home.join(".claude/projects")).unwrap();
+
+        let flag = format!("{},{}", first.display(), second.join("projects").display());
+        let env = format!("{}, {}", second.display(), home.join("missing").display());
+        let roots = resolve_claude_roots(Some(&flag), Some(&env), Some(home.path().to_path_buf()));
+
+        assert_eq!(roots, vec![first.join("projects"), second.join("projects")]);
+    }
+
+    #[test]
+    fn test_encode_project_path() {
+        assert_eq!(encode_project_path(Path::new("/home/me/my.repo")), "-home-me-my-repo");
+        assert_eq!(encode_project_path(Path::new("/srv/app_v2")), "-srv-app-v2");
+    }
+
+    #[test]
+    fn test_decode_project_dir_name_uses_filesystem() {
+        let home = TempDir::new("decode");
+        std::fs::create_dir_all(home.join("my-repo.rs/sub")).unwrap();
+        let encoded = encode_project_path(&home.join("my-repo.rs/sub"));
+
+        assert_eq!(decode_project_dir_name(&encoded), home.join("my-repo.rs/sub"));
+        assert_eq!(decode_project_dir_name("-nonexistent-path-x"), PathBuf::from("/nonexistent/path/x"));
+    }
+
+    #[test]
+    fn test_input_files_from_dirs_and_globs() {
+        let dir = TempDir::new("inputs");
+        let ci = dir.join("ci").join("-home-runner-repo");
+        std::fs::create_dir_all(&ci).unwrap();
+        std::fs::write(ci.join("a.jsonl"), "").unwrap();
+        std::fs::write(ci.join("b.jsonl.gz"), "").unwrap();
+        std::fs::write(ci.join("notes.txt"), "").unwrap();
+        let copied = dir.join("copied");
+        std::fs::create_dir_all(&copied).unwrap();
+        std::fs::write(copied.join("x.jsonl"), "").unwrap();
+        std::fs::write(copied.join("y.jsonl"), "").unwrap();
+
+        let sources = DataSourceOptions {
+            inputs: vec![
+                dir.join("ci").to_string_lossy().into_owned(),
+                dir.join("copied").join("*.jsonl").to_string_lossy().into_owned(),
+                ci.join("a.jsonl").to_string_lossy().into_owned(),
+            ],
+            ..Default::default()
+        };
+        let names: Vec<String> = sources
+            .input_files()
+            .unwrap()
+            .iter()
+            .map(|file| file.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["a.jsonl", "b.jsonl.gz", "x.jsonl", "y.jsonl"]);
+
+        let missing = DataSourceOptions {
+            inputs: vec![dir.join("nothing-*.jsonl").to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        assert!(missing.input_files().is_err());
+    }
+
+    #[test]
+    fn test_project_filter_matches_most_specific_project() {
+        let dirs = vec![
+            PathBuf::from("/root/projects/-home-me"),
+            PathBuf::from("/root/projects/-home-me-repo"),
+            PathBuf::from("/root/projects/-home-me-other"),
+        ];
+
+        let filter = ProjectFilter::Path(PathBuf::from("/home/me/repo/src"));
+        assert_eq!(filter.apply(dirs.clone()), vec![PathBuf::from("/root/projects/-home-me-repo")]);
+
+        let filter = ProjectFilter::Name("other".to_string());
+        assert_eq!(filter.apply(dirs.clone()), vec![PathBuf::from("/root/projects/-home-me-other")]);
+
+        let filter = ProjectFilter::Path(PathBuf::from("/srv/elsewhere"));
+        assert!(filter.apply(dirs).is_empty());
+    }
+
+    #[test]
+    fn test_project_name_does_not_match_longer_names() {
+        let home = TempDir::new("project-name");
+        for repo in ["repo", "my-repo", "other.repo"] {
+            std::fs::create_dir_all(home.join(repo)).unwrap();
+        }
+        let dirs: Vec<PathBuf> = ["repo", "my-repo", "other.repo"]
+            .iter()
+            .map(|repo| PathBuf::from("/root/projects").join(encode_project_path(&home.join(repo))))
+            .collect();
+
+        let filter = ProjectFilter::Name("repo".to_string());
+        assert_eq!(filter.apply(dirs.clone()), vec![dirs[0].clone()]);
+
+        let filter = ProjectFilter::Name("my-repo".to_string());
+        assert_eq!(filter.apply(dirs.clone()), vec![dirs[1].clone()]);
+
+        // Each project name is decoded once, however many records carry it
+        let sources = DataSourceOptions {
+            project_filter: Some(filter),
+            ..Default::default()
+        };
+        let my_repo = dir_name(&dirs[1]).to_string();
+        assert!(sources.includes_project(&my_repo));
+        std::fs::remove_dir_all(home.path()).unwrap();
+        assert!(sources.includes_project(&my_repo));
+        assert!(!sources.includes_project(dir_name(&dirs[0])));
+    }
+}
@@ -0,0 +1,320 @@
+//! # Doctor Module
+//!
+//! Diagnostics over the raw session files, explaining what the reports skip and why
+//!
+//! ## Key Components
+//! - [`DoctorReport`] - Resolved data roots plus per-file diagnostics
+//! - [`FileDiagnostics`] - Line-level findings for a single JSONL file
+//! - [`diagnose`] - Scan files in report order with the same deduplication as the reports
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use crate::entry_processor::{LineIssue, create_unique_hash, project_name_for_file};
+use crate::jsonl_parser::{SessionEntry, is_metadata_record};
+use crate::pricing::{PricingSource, calculates_cost, pricing_source_at};
+use crate::session_log::for_each_stream;
+
+/// Findings for one session file
+#[derive(Debug, Default, Serialize)]
+pub struct FileDiagnostics {
+    pub path: PathBuf,
+    pub project: String,
+    /// Non-empty lines read
+    pub lines_read: usize,
+    /// Entries carrying token usage
+    pub usage_entries: usize,
+    /// Valid JSON records that are not messages (e.g. conversation summaries)
+    pub metadata_lines: usize,
+    /// Lines that are not valid session entries and were skipped
+    pub unparseable: Vec<LineIssue>,
+    /// Usage entries without `requestId` or `message.id`, which cannot be deduplicated
+    pub missing_ids: Vec<usize>,
+    /// Usage entries dropped because an identical entry was seen earlier
+    pub duplicates_dropped: usize,
+    /// Usage entries whose timestamp is not valid RFC 3339
+    pub bad_timestamps: Vec<LineIssue>,
+    /// The file could not be read at all
+    pub read_error: Option<String>,
+}
+
+impl FileDiagnostics {
+    pub fn has_issues(&self) -> bool {
+        !self.unparseable.is_empty()
+            || !self.missing_ids.is_empty()
+            || self.duplicates_dropped > 0
+            || !self.bad_timestamps.is_empty()
+            || self.read_error.is_some()
+    }
+}
+
+/// Everything `doctor` found
+#[derive(Debug, Default, Serialize)]
+pub struct DoctorReport {
+    pub roots: Vec<PathBuf>,
+    /// Explicit `--input` sources, which replace the roots when given
+    pub inputs: Vec<String>,
+    pub project_count: usize,
+    pub files: Vec<FileDiagnostics>,
+    /// Models priced with the fallback rate, with the number of entries affected
+    pub unknown_models: BTreeMap<String, usize>,
+}
+
+impl DoctorReport {
+    pub fn total_lines(&self) -> usize {
+        self.files.iter().map(|file| file.lines_read).sum()
+    }
+
+    pub fn total_usage_entries(&self) -> usize {
+        self.files.iter().map(|file| file.usage_entries).sum()
+    }
+
+    pub fn total_unparseable(&self) -> usize {
+        self.files.iter().map(|file| file.unparseable.len()).sum()
+    }
+
+    pub fn total_missing_ids(&self) -> usize {
+        self.files.iter().map(|file| file.missing_ids.len()).sum()
+    }
+
+    pub fn total_duplicates(&self) -> usize {
+        self.files.iter().map(|file| file.duplicates_dropped).sum()
+    }
+
+    pub fn total_bad_timestamps(&self) -> usize {
+        self.files.iter().map(|file| file.bad_timestamps.len()).sum()
+    }
+}
+
+/// Diagnose session files in order, deduplicating across files like the reports do
+pub fn diagnose(roots: Vec<PathBuf>, project_count: usize, session_files: &[PathBuf]) -> DoctorReport {
+    let mut report = DoctorReport {
+        roots,
+        project_count,
+        ..Default::default()
+    };
+    let mut seen_hashes = HashSet::new();
+
+    for path in session_files {
+        let mut diagnostics = FileDiagnostics {
+            path: path.clone(),
+            project: project_name_for_file(path),
+            ..Default::default()
+        };
+
+        if let Err(e) = diagnose_file(path, &mut diagnostics, &mut seen_hashes, &mut report.unknown_models) {
+            diagnostics.read_error = Some(format!("{:#}", e));
+        }
+        report.files.push(diagnostics);
+    }
+
+    report
+}
+
+fn diagnose_file(
+    path: &Path,
+    diagnostics: &mut FileDiagnostics,
+    seen_hashes: &mut HashSet<String>,
+    unknown_models: &mut BTreeMap<String, usize>,
+) -> Result<()> {
+    for_each_stream(path, |stream| {
+        // Issues inside archives name the member they were found in
+        let location = |message: String| match stream.member {
+            Some(member) => format!("{}: {}", member.display(), message),
+            None => message,
+        };
+
+        for (index, line) in stream.reader.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.with_context(|| format!("Failed to read line {}", line_number))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            diagnostics.lines_read += 1;
+
+            let entry = match serde_json::from_str::<SessionEntry>(&line) {
+                Ok(entry) => entry,
+                Err(_) if is_metadata_record(&line) => {
+                    diagnostics.metadata_lines += 1;
+                    continue;
+                }
+                Err(e) => {
+                    diagnostics.unparseable.push(LineIssue {
+                        line: line_number,
+                        message: location(e.to_string()),
+                    });
+                    continue;
+                }
+            };
+
+            let hash = create_unique_hash(&entry);
+
+            // Same selection as the reports: usage data from a real model
+            let Some((model, cost_usd)) = entry.message.as_ref().and_then(|message| {
+                match (&message.model, &message.usage) {
+                    (Some(model), Some(_)) if model != "<synthetic>" => Some((model, message.cost_usd)),
+                    _ => None,
+                }
+            }) else {
+                // Entries without usage still claim their hash, as in the reports
+                if let Some(hash) = hash {
+                    seen_hashes.insert(hash);
+                }
+                continue;
+            };
+
+            diagnostics.usage_entries += 1;
+
+            if hash.is_none() {
+                diagnostics.missing_ids.push(line_number);
+            }
+
+            // The reports skip these before deduplication, so they never claim their hash
+            let timestamp = match DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(timestamp) => timestamp.with_timezone(&Utc),
+                Err(e) => {
+                    diagnostics.bad_timestamps.push(LineIssue {
+                        line: line_number,
+                        message: location(format!("'{}': {}", entry.timestamp, e)),
+                    });
+                    continue;
+                }
+            };
+
+            if hash.is_some_and(|hash| !seen_hashes.insert(hash)) {
+                diagnostics.duplicates_dropped += 1;
+                continue;
+            }
+
+            // Same pricing as the reports: only calculated costs use the rates in effect at the entry
+            if calculates_cost(cost_usd) && pricing_source_at(model, timestamp) != PricingSource::Known {
+                *unknown_models.entry(model.clone()).or_default() += 1;
+            }
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn usage_line(request: &str, model: &str, timestamp: &str) -> String {
+        format!(
+            r#"{{"sessionId":"s1","timestamp":"{}","requestId":"{}","message":{{"id":"m-{}","model":"{}","usage":{{"input_tokens":10,"output_tokens":20}}}}}}"#,
+            timestamp, request, request, model
+        )
+    }
+
+    #[test]
+    fn test_diagnose_reports_each_kind_of_issue() {
+        let dir = TempDir::new("doctor");
+        let project = dir.join("-home-me-repo");
+        std::fs::create_dir_all(&project).unwrap();
+
+        let first = project.join("a.jsonl");
+        let second = project.join("b.jsonl");
+        std::fs::write(
+            &first,
+            [
+                usage_line("r1", "claude-sonnet-4-20250514", "2025-06-01T10:00:00Z"),
+                "{not json".to_string(),
+                r#"{"type":"summary","summary":"Refactor","leafUuid":"u1"}"#.to_string(),
+                usage_line("r2", "claude-mystery-9", "2025-06-01T10:01:00Z"),
+                r#"{"timestamp":"2025-06-01T10:02:00Z","message":{"model":"claude-opus-4-20250514","usage":{"input_tokens":1}}}"#.to_string(),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        std::fs::write(
+            &second,
+            [
+                usage_line("r1", "claude-sonnet-4-20250514", "2025-06-01T10:00:00Z"),
+                usage_line("r3", "claude-sonnet-4-20250514", "yesterday"),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let report = diagnose(vec![dir.path().to_path_buf()], 1, &[first, second]);
+
+        let a = &report.files[0];
+        assert_eq!(a.lines_read, 5);
+        assert_eq!(a.usage_entries, 3);
+        assert_eq!(a.metadata_lines, 1);
+        assert_eq!(a.unparseable.len(), 1);
+        assert_eq!(a.unparseable[0].line, 2);
+        assert_eq!(a.missing_ids, vec![5]);
+
+        let b = &report.files[1];
+        assert_eq!(b.duplicates_dropped, 1);
+        assert_eq!(b.bad_timestamps[0].line, 2);
+
+        assert_eq!(report.unknown_models.get("claude-mystery-9"), Some(&1));
+        assert_eq!(report.unknown_models.len(), 1);
+    }
+
+    #[test]
+    fn test_bad_timestamp_does_not_claim_the_duplicate() {
+        let dir = TempDir::new("doctor-dedup");
+        let project = dir.join("-home-me-repo");
+        std::fs::create_dir_all(&project).unwrap();
+
+        let first = project.join("a.jsonl");
+        let second = project.join("b.jsonl");
+        std::fs::write(&first, usage_line("r1", "claude-sonnet-4-20250514", "yesterday")).unwrap();
+        std::fs::write(&second, usage_line("r1", "claude-sonnet-4-20250514", "2025-06-01T10:00:00Z")).unwrap();
+        let files = [first, second];
+
+        // The reports bill the second copy, so it is not a duplicate
+        let report = diagnose(vec![dir.path().to_path_buf()], 1, &files);
+        assert_eq!(report.total_bad_timestamps(), 1);
+        assert_eq!(report.total_duplicates(), 0);
+
+        let entries = crate::entry_processor::load_all_entries(&files, &Default::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_models_are_checked_at_the_entry_date() {
+        // The installed table is process-wide; its pattern only matches this test's model
+        let table = crate::pricing::PricingTable::parse(
+            r#"[[models]]
+            model = "claude-doctor-dated*"
+            input = 1.0
+            output = 2.0
+            effective_until = 2025-07-01"#,
+            false,
+        )
+        .unwrap();
+        crate::pricing::install_pricing(table);
+
+        let dir = TempDir::new("doctor-dated");
+        let project = dir.join("-home-me-repo");
+        std::fs::create_dir_all(&project).unwrap();
+        let file = project.join("a.jsonl");
+        let recorded = usage_line("r3", "claude-doctor-dated-1", "2025-08-02T10:00:00Z")
+            .replace(r#""usage""#, r#""costUSD":0.5,"usage""#);
+        std::fs::write(
+            &file,
+            [
+                usage_line("r1", "claude-doctor-dated-1", "2025-06-01T10:00:00Z"),
+                usage_line("r2", "claude-doctor-dated-1", "2025-08-01T10:00:00Z"),
+                recorded,
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        // Only the calculated entry past the rule's end falls back
+        let report = diagnose(vec![dir.path().to_path_buf()], 1, &[file]);
+        assert_eq!(report.files[0].usage_entries, 3);
+        assert_eq!(report.unknown_models.get("claude-doctor-dated-1"), Some(&1));
+    }
+}
@@ -4,29 +4,121 @@
 //!
 //! ## Key Components
 //! - [`process_all_entries`] - Process all JSONL entries with global deduplication
+//! - [`load_all_entries`] - Load deduplicated entries without aggregating them
+//! - [`load_sessions`] - Build sessions from the same deduplicated records
+//! - [`sessions_from_records`] - Build sessions from files parsed elsewhere (the monitor's tail reader)
+//! - [`parse_file_records`] - Extract per-file [`UsageRecord`]s and malformed lines (cached between runs)
+//! - [`parse_lines`] - Extract records from a run of lines, e.g. those appended to a file
 //! - [`aggregate_entries_by_date`] - Group and aggregate entries by date
+//! - [`aggregate_entries_by_month`] - Group and aggregate entries by month
+//! - [`aggregate_entries_by_project`] - Group and aggregate entries by project
+//! - [`aggregate_entries_by_version`] - Group and aggregate entries by Claude Code version
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
 
-use crate::jsonl_parser::{SessionEntry, Usage};
-use crate::pricing::calculate_cost_from_tokens;
-use crate::table_display::{DailyStats, ModelBreakdown};
+use crate::cache::EntryCache;
+use crate::aggregation::{UsageTotals, group_entries};
+use crate::cli::GroupBy;
+use crate::data_processing::{MonthlyStats, ProjectStats, VersionStats};
+use crate::data_sources::{DataSourceOptions, decode_project_dir_name};
+use crate::jsonl_parser::{ModelUsage, SessionData, SessionEntry, Usage, is_metadata_record};
+use crate::models::calculate_weighted_tokens;
+use crate::session_log::{for_each_stream, is_archive};
+use crate::pricing::{PricingSource, calculates_cost, check_unknown_models, entry_cost, is_long_context_request};
+use crate::table_display::{DailyStats, ModelBreakdown, simplify_model_name};
 
 #[derive(Debug)]
 pub struct ProcessedEntry {
     pub date: String,
+    pub timestamp: DateTime<Local>,
     pub model: String,
     pub usage: Usage,
     pub cost: f64,
+    /// Claude project directory name the entry was read from
+    pub project: String,
+    /// Working directory recorded in the entry
+    pub cwd: String,
+    pub session_id: String,
+    /// Whether the request was made by a sidechain (Task subagent)
+    pub is_sidechain: bool,
+    /// Claude Code version that wrote the entry
+    pub version: String,
+    /// Whether the cost comes from known rates or from `--unknown-model` handling
+    pub pricing_source: PricingSource,
+    /// Whether the request was billed at long-context (premium tier) rates
+    pub long_context: bool,
+}
+
+/// Usage-relevant data extracted from one JSONL line, before deduplication
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// `message.id:requestId` deduplication key
+    pub hash: Option<String>,
+    /// Whether the entry carries usage; other entries only claim their hash
+    pub billable: bool,
+    pub timestamp: DateTime<Utc>,
+    pub model: String,
+    pub usage: Usage,
+    /// Cost embedded in the log entry, if any
+    pub cost_usd: Option<f64>,
+    pub project: String,
+    pub cwd: String,
+    pub session_id: String,
+    pub is_sidechain: bool,
+    pub version: String,
+    /// Message id within the conversation tree, empty if the entry has none
+    pub uuid: String,
+    /// Parent message, or the logical parent of a compacted conversation's new root
+    pub parent_uuid: Option<String>,
+    /// `tool_use` blocks of an assistant message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_uses: Vec<ToolUse>,
+    /// `tool_use` ids whose `tool_result` block was flagged `is_error`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_tool_uses: Vec<String>,
+}
+
+/// One `tool_use` content block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUse {
+    pub id: String,
+    pub name: String,
+}
+
+/// A line that could not be used, with its 1-based line number
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Everything extracted from one JSONL file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileRecords {
+    pub records: Vec<UsageRecord>,
+    /// Time span of every session appearing in the file, over all of its entries
+    pub sessions: Vec<SessionSpan>,
+    /// Lines skipped because they are not valid entries or carry an invalid timestamp
+    pub malformed: Vec<LineIssue>,
+}
+
+/// First and last timestamp of one session's entries within a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSpan {
+    pub session_id: String,
+    pub project: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
 }
 
 /// Create unique hash for entry deduplication (matching ccusage logic exactly)
-fn create_unique_hash(entry: &SessionEntry) -> Option<String> {
+#[allow(clippy::collapsible_if)]
+pub fn create_unique_hash(entry: &SessionEntry) -> Option<String> {
     if let Some(message) = &entry.message {
         if let (Some(message_id), Some(request_id)) = (&message.id, &entry.request_id) {
             return Some(format!("{}:{}", message_id, request_id));
@@ -36,80 +128,375 @@ fn create_unique_hash(entry: &SessionEntry) -> Option<String> {
 }
 
 /// Process all JSONL files with global entry-level deduplication (matching ccusage)
-pub fn process_all_entries(session_files: &[std::path::PathBuf]) -> Result<Vec<DailyStats>> {
-    let mut global_processed_hashes = HashSet::new();
+pub fn process_all_entries(session_files: &[PathBuf], sources: &DataSourceOptions) -> Result<Vec<DailyStats>> {
+    let all_entries = load_all_entries(session_files, sources)?;
+    
+    // Group entries by date and aggregate
+    aggregate_entries_by_date(all_entries)
+}
+
+/// Load all entries with usage data, deduplicated across every file
+pub fn load_all_entries(session_files: &[PathBuf], sources: &DataSourceOptions) -> Result<Vec<ProcessedEntry>> {
     let mut all_entries = Vec::new();
+    for_each_unique_record(session_files, sources, |_| {}, |record| all_entries.push(processed_entry(record)))?;
+    check_unknown_models()?;
+    Ok(all_entries)
+}
+
+/// Load sessions from the same deduplicated records as the usage reports
+///
+/// Sessions are keyed by session id across all files, so a session resumed in
+/// another file, or a file holding several sessions, is grouped correctly. Start
+/// and end cover every entry of the session, not only those carrying usage.
+pub fn load_sessions(session_files: &[PathBuf], sources: &DataSourceOptions) -> Result<Vec<SessionData>> {
+    let mut entries = Vec::new();
+    let mut spans = HashMap::new();
+    
+    for_each_unique_record(
+        session_files,
+        sources,
+        |parsed| add_session_spans(&mut spans, parsed, sources),
+        |record| entries.push(processed_entry(record)),
+    )?;
+    check_unknown_models()?;
     
-    // Process files sequentially to maintain global hash consistency (like ccusage)
-    for file in session_files {
-        if let Err(e) = process_file_entries(file, &mut global_processed_hashes, &mut all_entries) {
-            eprintln!("Warning: Failed to process file {}: {}", file.display(), e);
+    Ok(group_sessions(&entries, &spans))
+}
+
+/// Build sessions from files that were already parsed, in the given order
+///
+/// Records are deduplicated and grouped exactly as in [`load_sessions`], so the
+/// monitor, which parses appended lines itself, shows the same sessions.
+pub fn sessions_from_records<'a>(
+    files: impl IntoIterator<Item = (&'a Path, &'a FileRecords)>,
+    sources: &DataSourceOptions,
+) -> Result<Vec<SessionData>> {
+    let mut entries = Vec::new();
+    let mut spans = HashMap::new();
+    
+    visit_file_records(
+        files,
+        sources,
+        |parsed| add_session_spans(&mut spans, parsed, sources),
+        |record, counted| {
+            if counted {
+                entries.push(processed_entry(record));
+            }
+        },
+    )?;
+    check_unknown_models()?;
+    
+    Ok(group_sessions(&entries, &spans))
+}
+
+/// Widen each session's span with a file's spans, respecting the project filter
+fn add_session_spans(
+    spans: &mut HashMap<String, (DateTime<Utc>, DateTime<Utc>)>,
+    parsed: &FileRecords,
+    sources: &DataSourceOptions,
+) {
+    for span in parsed.sessions.iter().filter(|span| sources.includes_project(&span.project)) {
+        let (start, end) = spans.entry(span.session_id.clone()).or_insert((span.start, span.end));
+        *start = (*start).min(span.start);
+        *end = (*end).max(span.end);
+    }
+}
+
+/// Group entries by session id, each session covering at least its span
+fn group_sessions(entries: &[ProcessedEntry], spans: &HashMap<String, (DateTime<Utc>, DateTime<Utc>)>) -> Vec<SessionData> {
+    group_entries(entries, &[GroupBy::Session, GroupBy::Model])
+        .into_iter()
+        .map(|group| {
+            // Every group holds at least one entry
+            let first_seen = group.totals.first_seen.unwrap_or_default().with_timezone(&Utc);
+            let last_seen = group.totals.last_seen.unwrap_or_default().with_timezone(&Utc);
+            
+            let mut session = SessionData::new(group.raw_key.clone(), first_seen);
+            session.end_time = Some(last_seen);
+            if let Some(&(start, end)) = spans.get(&group.raw_key) {
+                session.start_time = session.start_time.min(start);
+                session.end_time = session.end_time.max(Some(end));
+            }
+            for model in &group.groups {
+                session.model_usage.insert(model.key.clone(), model_usage_from_totals(&model.key, &model.totals));
+            }
+            session.sidechain = group.totals.sidechain;
+            session.long_context_cost_usd = group.totals.long_context_cost_usd;
+            session.calculate_totals();
+            session
+        })
+        .collect()
+}
+
+/// Per-model session usage from the totals of a model group
+fn model_usage_from_totals(model_name: &str, totals: &UsageTotals) -> ModelUsage {
+    ModelUsage {
+        model_name: model_name.to_string(),
+        total_input: totals.input_tokens,
+        total_output: totals.output_tokens,
+        total_cache_write: totals.cache_creation_tokens,
+        total_cache_write_5m: totals.cache_creation_5m_tokens,
+        total_cache_write_1h: totals.cache_creation_1h_tokens,
+        total_cache_read: totals.cache_read_tokens,
+        message_count: totals.entry_count as u32,
+        weighted_tokens: calculate_weighted_tokens(model_name, totals.input_tokens + totals.output_tokens),
+        cost_usd: totals.cost_usd,
+        pricing_source: totals.pricing_source,
+    }
+}
+
+/// Visit every billable record once, in file order, applying global deduplication
+///
+/// `on_file` sees each readable file's parsed data before its records are visited.
+fn for_each_unique_record(
+    session_files: &[PathBuf],
+    sources: &DataSourceOptions,
+    on_file: impl FnMut(&FileRecords),
+    mut visit: impl FnMut(&UsageRecord),
+) -> Result<()> {
+    for_each_record(session_files, sources, on_file, |record, counted| {
+        if counted {
+            visit(record);
         }
+    })
+}
+
+/// Visit every record in file order, with whether it counts towards usage
+///
+/// A record counts if it is billable and the first occurrence of its hash.
+/// Parsed records are reused from the on-disk cache for files that have not
+/// changed since they were last read, unless caching is disabled. Changed files
+/// are parsed in parallel; deduplication then walks the records in file order, so
+/// the first occurrence of a hash wins exactly as in a sequential pass.
+///
+/// Malformed lines are skipped and counted, or fail the load in strict mode.
+///
+/// `on_file` sees each readable file's parsed data before its records are visited.
+pub fn for_each_record(
+    session_files: &[PathBuf],
+    sources: &DataSourceOptions,
+    on_file: impl FnMut(&FileRecords),
+    visit: impl FnMut(&UsageRecord, bool),
+) -> Result<()> {
+    let mut cache = if sources.use_cache { EntryCache::load() } else { EntryCache::disabled() };
+    if let Some(stdin) = sources.stdin_file() {
+        cache.exclude(stdin);
     }
+    let mut readable = Vec::new();
     
-    // Group entries by date and aggregate
-    aggregate_entries_by_date(all_entries)
+    for (file, parsed) in session_files.iter().zip(cache.records_for_all(session_files, parse_file_records)) {
+        match parsed {
+            Ok(parsed) => readable.push((file.as_path(), parsed)),
+            Err(e) if sources.strict => {
+                return Err(e.context(format!("Failed to process file {}", file.display())));
+            }
+            Err(e) => eprintln!("Warning: Failed to process file {}: {}", file.display(), e),
+        }
+    }
+    
+    let (skipped_lines, files_with_skipped_lines) = visit_file_records(readable, sources, on_file, visit)?;
+    if skipped_lines > 0 {
+        eprintln!(
+            "Warning: Skipped {} malformed line(s) in {} file(s); run `ccusage-rs doctor` for details",
+            skipped_lines, files_with_skipped_lines
+        );
+    }
+    
+    if let Err(e) = cache.save() {
+        log::debug!("Failed to save entry cache: {}", e);
+    }
+    
+    Ok(())
 }
 
-fn process_file_entries(
-    file_path: &Path,
-    processed_hashes: &mut HashSet<String>,
-    all_entries: &mut Vec<ProcessedEntry>,
+/// Visit the records of parsed files in order, deduplicating as in [`for_each_record`]
+///
+/// Returns the number of malformed lines skipped and of files they were in.
+fn visit_file_records<'a>(
+    files: impl IntoIterator<Item = (&'a Path, &'a FileRecords)>,
+    sources: &DataSourceOptions,
+    mut on_file: impl FnMut(&FileRecords),
+    mut visit: impl FnMut(&UsageRecord, bool),
+) -> Result<(usize, usize)> {
+    let mut global_processed_hashes = HashSet::new();
+    let mut skipped_lines = 0;
+    let mut files_with_skipped_lines = 0;
+    
+    // Deduplicate sequentially to maintain global hash consistency (like ccusage)
+    for (file, parsed) in files {
+        if let Some(issue) = parsed.malformed.first() {
+            if sources.strict {
+                anyhow::bail!(
+                    "{}:{}: {} (run without --strict to skip malformed lines)",
+                    file.display(),
+                    issue.line,
+                    issue.message
+                );
+            }
+            skipped_lines += parsed.malformed.len();
+            files_with_skipped_lines += 1;
+        }
+        
+        on_file(parsed);
+        
+        // Archives mix projects, so the project filter applies per record
+        let filter_records = is_archive(file) && sources.project_filter.is_some();
+        
+        for record in &parsed.records {
+            if filter_records && !sources.includes_project(&record.project) {
+                continue;
+            }
+            let counted = claim_unique(record, &mut global_processed_hashes) && record.billable;
+            visit(record, counted);
+        }
+    }
+    
+    Ok((skipped_lines, files_with_skipped_lines))
+}
+
+/// Extract the usage records of one log file or archive, without deduplication
+///
+/// Lines that are not valid entries, and usage entries with an invalid
+/// timestamp, are recorded as malformed instead of failing the file.
+pub fn parse_file_records(file_path: &Path) -> Result<FileRecords> {
+    let mut parsed = FileRecords::default();
+    
+    for_each_stream(file_path, |stream| {
+        parse_lines(&mut parsed, stream.reader.lines(), 1, &stream.project, stream.member)
+    })?;
+    
+    Ok(parsed)
+}
+
+/// Add the records of consecutive lines to `parsed`, numbering them from `first_line`
+///
+/// Sessions already in `parsed` for the same project are widened rather than
+/// repeated, so a file can be parsed in pieces as it grows.
+pub fn parse_lines(
+    parsed: &mut FileRecords,
+    lines: impl IntoIterator<Item = std::io::Result<String>>,
+    first_line: usize,
+    project: &str,
+    member: Option<&Path>,
 ) -> Result<()> {
-    let file = File::open(file_path).context("Failed to open JSONL file")?;
-    let reader = BufReader::new(file);
+    let mut span_positions: HashMap<String, usize> = parsed
+        .sessions
+        .iter()
+        .enumerate()
+        .filter(|(_, span)| span.project == project)
+        .map(|(position, span)| (span.session_id.clone(), position))
+        .collect();
     
-    for line in reader.lines() {
-        let line = line.context("Failed to read line")?;
+    // Line numbers of archive members are reported together with the member
+    let location = |message: String| match member {
+        Some(member) => format!("{}: {}", member.display(), message),
+        None => message,
+    };
+    
+    for (index, line) in lines.into_iter().enumerate() {
+        let line_number = first_line + index;
+        let line = line.with_context(|| format!("Failed to read line {}", line_number))?;
         if line.trim().is_empty() {
             continue;
         }
         
-        // Try to parse as SessionEntry
-        match serde_json::from_str::<SessionEntry>(&line) {
-            Ok(entry) => {
-                // Create unique hash for deduplication (matching ccusage logic)
-                if let Some(unique_hash) = create_unique_hash(&entry) {
-                    if processed_hashes.contains(&unique_hash) {
-                        continue; // Skip duplicate entry
-                    }
-                    processed_hashes.insert(unique_hash);
+        // Try to parse as SessionEntry; records without a message (e.g. summaries) are expected
+        let entry = match serde_json::from_str::<SessionEntry>(&line) {
+            Ok(entry) => entry,
+            Err(_) if is_metadata_record(&line) => continue,
+            Err(e) => {
+                parsed.malformed.push(LineIssue {
+                    line: line_number,
+                    message: location(format!("invalid entry: {}", e)),
+                });
+                continue;
+            }
+        };
+        
+        let entry_time = DateTime::parse_from_rfc3339(&entry.timestamp).map(|time| time.with_timezone(&Utc));
+        if let Ok(time) = entry_time
+            && !entry.session_id.is_empty()
+        {
+            match span_positions.get(&entry.session_id) {
+                Some(&position) => {
+                    let span = &mut parsed.sessions[position];
+                    span.start = span.start.min(time);
+                    span.end = span.end.max(time);
                 }
-                
-                // Process entry if it has usage data
-                if let Some(message) = &entry.message {
-                    if let (Some(model), Some(usage)) = (&message.model, &message.usage) {
-                        // Skip synthetic models (matching ccusage behavior)
-                        if model == "<synthetic>" {
-                            continue;
-                        }
-                        
-                        let timestamp = DateTime::parse_from_rfc3339(&entry.timestamp)
-                            .context("Failed to parse timestamp")?
-                            .with_timezone(&Local);
-                        
-                        let date = timestamp.format("%Y-%m-%d").to_string();
-                        
-                        // Calculate cost for this entry (matching our pricing logic)
-                        let cost = if let Some(existing_cost) = message.cost_usd {
-                            existing_cost
-                        } else {
-                            // Calculate cost using our pricing model
-                            calculate_entry_cost(model, usage)
-                        };
-                        
-                        all_entries.push(ProcessedEntry {
-                            date,
-                            model: model.clone(),
-                            usage: usage.clone(),
-                            cost,
+                None => {
+                    span_positions.insert(entry.session_id.clone(), parsed.sessions.len());
+                    parsed.sessions.push(SessionSpan {
+                        session_id: entry.session_id.clone(),
+                        project: project.to_string(),
+                        start: time,
+                        end: time,
+                    });
+                }
+            }
+        }
+        
+        // Create unique hash for deduplication (matching ccusage logic)
+        let hash = create_unique_hash(&entry);
+        // After compaction the new root points back through `logicalParentUuid`
+        let parent_uuid = entry.parent_uuid.clone().or_else(|| entry.logical_parent_uuid.clone());
+        let (tool_uses, failed_tool_uses) = tool_blocks(&entry);
+        
+        // Keep entry if it has usage data, skipping synthetic models (matching ccusage behavior)
+        let usage_data = entry.message.as_ref().and_then(|message| match (&message.model, &message.usage) {
+            (Some(model), Some(usage)) if model != "<synthetic>" => Some((message, model, usage)),
+            _ => None,
+        });
+        
+        match usage_data {
+            Some((message, model, usage)) => {
+                let timestamp = match entry_time {
+                    Ok(timestamp) => timestamp,
+                    Err(e) => {
+                        parsed.malformed.push(LineIssue {
+                            line: line_number,
+                            message: location(format!("invalid timestamp '{}': {}", entry.timestamp, e)),
                         });
+                        continue;
                     }
-                }
+                };
+                
+                parsed.records.push(UsageRecord {
+                    hash,
+                    billable: true,
+                    timestamp,
+                    model: model.clone(),
+                    usage: usage.clone(),
+                    cost_usd: message.cost_usd,
+                    project: project.to_string(),
+                    cwd: entry.cwd.clone(),
+                    session_id: entry.session_id.clone(),
+                    is_sidechain: entry.is_sidechain,
+                    version: entry.version.clone(),
+                    uuid: entry.uuid.clone(),
+                    parent_uuid,
+                    tool_uses,
+                    failed_tool_uses,
+                });
             }
-            Err(_) => {
-                // Skip invalid entries (like ccusage does)
-                continue;
+            // Entries without usage still claim their hash during deduplication,
+            // and messages are still nodes of the conversation tree
+            None => {
+                let node = entry_time.ok().filter(|_| !entry.uuid.is_empty() && !entry.session_id.is_empty());
+                if hash.is_none() && node.is_none() && tool_uses.is_empty() && failed_tool_uses.is_empty() {
+                    continue;
+                }
+                parsed.records.push(UsageRecord {
+                    hash,
+                    timestamp: node.unwrap_or_default(),
+                    project: project.to_string(),
+                    session_id: entry.session_id.clone(),
+                    is_sidechain: entry.is_sidechain,
+                    uuid: if node.is_some() { entry.uuid.clone() } else { String::new() },
+                    parent_uuid,
+                    tool_uses,
+                    failed_tool_uses,
+                    ..Default::default()
+                });
             }
         }
     }
@@ -117,92 +504,484 @@ fn process_file_entries(
     Ok(())
 }
 
-fn aggregate_entries_by_date(entries: Vec<ProcessedEntry>) -> Result<Vec<DailyStats>> {
-    let mut daily_map: HashMap<String, DailyStats> = HashMap::new();
-    
-    for entry in entries {
-        let daily_stat = daily_map
-            .entry(entry.date.clone())
-            .or_insert_with(|| DailyStats {
-                date: entry.date.clone(),
-                models: Vec::new(),
-                input_tokens: 0,
-                output_tokens: 0,
-                cache_creation_tokens: 0,
-                cache_read_tokens: 0,
-                total_tokens: 0,
-                cost_usd: 0.0,
-                model_breakdowns: Vec::new(),
-            });
-        
-        // Add model to list if not already present
-        let simplified_model = simplify_model_name(&entry.model);
-        if !daily_stat.models.contains(&simplified_model) {
-            daily_stat.models.push(simplified_model.clone());
-        }
-        
-        // Update per-model breakdown
-        if let Some(breakdown) = daily_stat.model_breakdowns.iter_mut().find(|b| b.model_name == simplified_model) {
-            breakdown.input_tokens += entry.usage.input_tokens;
-            breakdown.output_tokens += entry.usage.output_tokens;
-            breakdown.cache_creation_tokens += entry.usage.cache_creation_input_tokens;
-            breakdown.cache_read_tokens += entry.usage.cache_read_input_tokens;
-            breakdown.total_tokens += entry.usage.input_tokens 
-                + entry.usage.output_tokens 
-                + entry.usage.cache_creation_input_tokens 
-                + entry.usage.cache_read_input_tokens;
-            breakdown.cost_usd += entry.cost;
-        } else {
-            daily_stat.model_breakdowns.push(ModelBreakdown {
-                model_name: simplified_model,
-                input_tokens: entry.usage.input_tokens,
-                output_tokens: entry.usage.output_tokens,
-                cache_creation_tokens: entry.usage.cache_creation_input_tokens,
-                cache_read_tokens: entry.usage.cache_read_input_tokens,
-                total_tokens: entry.usage.input_tokens 
-                    + entry.usage.output_tokens 
-                    + entry.usage.cache_creation_input_tokens 
-                    + entry.usage.cache_read_input_tokens,
-                cost_usd: entry.cost,
-            });
+/// `tool_use` blocks of an assistant message, and the ids of failed `tool_result`
+/// blocks of any other message
+fn tool_blocks(entry: &SessionEntry) -> (Vec<ToolUse>, Vec<String>) {
+    let mut tool_uses = Vec::new();
+    let mut failed_tool_uses = Vec::new();
+    let Some(message) = &entry.message else {
+        return (tool_uses, failed_tool_uses);
+    };
+    let blocks = message.content.as_ref().and_then(|content| content.as_array());
+    
+    for block in blocks.into_iter().flatten() {
+        let block_type = block.get("type").and_then(|t| t.as_str());
+        if message.role == "assistant" {
+            if block_type == Some("tool_use")
+                && let (Some(id), Some(name)) = (
+                    block.get("id").and_then(|id| id.as_str()),
+                    block.get("name").and_then(|name| name.as_str()),
+                )
+            {
+                tool_uses.push(ToolUse {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                });
+            }
+        } else if block_type == Some("tool_result")
+            && block.get("is_error").and_then(|e| e.as_bool()) == Some(true)
+            && let Some(id) = block.get("tool_use_id").and_then(|id| id.as_str())
+        {
+            failed_tool_uses.push(id.to_string());
         }
-        
-        // Add token counts to totals
-        daily_stat.input_tokens += entry.usage.input_tokens;
-        daily_stat.output_tokens += entry.usage.output_tokens;
-        daily_stat.cache_creation_tokens += entry.usage.cache_creation_input_tokens;
-        daily_stat.cache_read_tokens += entry.usage.cache_read_input_tokens;
-        daily_stat.cost_usd += entry.cost;
     }
     
-    // Update totals
-    for daily_stat in daily_map.values_mut() {
-        daily_stat.total_tokens = daily_stat.input_tokens
-            + daily_stat.output_tokens
-            + daily_stat.cache_creation_tokens
-            + daily_stat.cache_read_tokens;
+    (tool_uses, failed_tool_uses)
+}
+
+/// Claim a record's deduplication hash, returning false if it was already seen
+fn claim_unique(record: &UsageRecord, processed_hashes: &mut HashSet<String>) -> bool {
+    match &record.hash {
+        Some(unique_hash) => processed_hashes.insert(unique_hash.clone()),
+        None => true,
     }
+}
+
+/// Turn a billable record into a processed entry
+fn processed_entry(record: &UsageRecord) -> ProcessedEntry {
+    let timestamp = record.timestamp.with_timezone(&Local);
+    
+    // Recorded or calculated cost, depending on `--mode`
+    let (cost, pricing_source) = entry_cost(&record.usage, &record.model, record.cost_usd, record.timestamp);
     
-    // Convert to sorted vector
-    let mut daily_stats: Vec<DailyStats> = daily_map.into_values().collect();
-    daily_stats.sort_by(|a, b| a.date.cmp(&b.date));
+    ProcessedEntry {
+        date: timestamp.format("%Y-%m-%d").to_string(),
+        timestamp,
+        model: record.model.clone(),
+        usage: record.usage.clone(),
+        cost,
+        project: record.project.clone(),
+        cwd: record.cwd.clone(),
+        session_id: record.session_id.clone(),
+        is_sidechain: record.is_sidechain,
+        version: record.version.clone(),
+        pricing_source,
+        // Only calculated costs can include the long-context premium; `--mode display` never prices
+        long_context: calculates_cost(record.cost_usd)
+            && is_long_context_request(&record.usage, &record.model, record.timestamp),
+    }
+}
+
+fn aggregate_entries_by_date(entries: Vec<ProcessedEntry>) -> Result<Vec<DailyStats>> {
+    let days = group_entries(&entries, &[GroupBy::Day, GroupBy::Model]);
+    
+    let daily_stats = days
+        .into_iter()
+        .map(|day| {
+            // Per-model breakdown under simplified names, so model versions share a row
+            let mut model_breakdowns: Vec<ModelBreakdown> = Vec::new();
+            for model in &day.groups {
+                let model_name = simplify_model_name(&model.key);
+                let position = match model_breakdowns.iter().position(|b| b.model_name == model_name) {
+                    Some(position) => position,
+                    None => {
+                        model_breakdowns.push(ModelBreakdown {
+                            model_name,
+                            ..Default::default()
+                        });
+                        model_breakdowns.len() - 1
+                    }
+                };
+                let breakdown = &mut model_breakdowns[position];
+                breakdown.input_tokens += model.totals.input_tokens;
+                breakdown.output_tokens += model.totals.output_tokens;
+                breakdown.cache_creation_tokens += model.totals.cache_creation_tokens;
+                breakdown.cache_creation_1h_tokens += model.totals.cache_creation_1h_tokens;
+                breakdown.cache_read_tokens += model.totals.cache_read_tokens;
+                breakdown.total_tokens += model.totals.total_tokens;
+                breakdown.cost_usd += model.totals.cost_usd;
+            }
+            
+            DailyStats {
+                date: day.key,
+                models: day.totals.models,
+                input_tokens: day.totals.input_tokens,
+                output_tokens: day.totals.output_tokens,
+                cache_creation_tokens: day.totals.cache_creation_tokens,
+                cache_creation_1h_tokens: day.totals.cache_creation_1h_tokens,
+                cache_read_tokens: day.totals.cache_read_tokens,
+                total_tokens: day.totals.total_tokens,
+                cost_usd: day.totals.cost_usd,
+                model_breakdowns,
+                sidechain: day.totals.sidechain,
+                pricing_source: day.totals.pricing_source,
+                long_context_cost_usd: day.totals.long_context_cost_usd,
+            }
+        })
+        .collect();
     
     Ok(daily_stats)
 }
 
-fn calculate_entry_cost(model: &str, usage: &Usage) -> f64 {
-    calculate_cost_from_tokens(usage, model)
+/// Aggregate entries per calendar month, oldest first
+pub fn aggregate_entries_by_month(entries: Vec<ProcessedEntry>) -> Vec<MonthlyStats> {
+    group_entries(&entries, &[GroupBy::Month])
+        .into_iter()
+        .map(|month| MonthlyStats {
+            month: month.key,
+            models: month.totals.models,
+            input_tokens: month.totals.input_tokens,
+            output_tokens: month.totals.output_tokens,
+            cache_creation_tokens: month.totals.cache_creation_tokens,
+            cache_creation_1h_tokens: month.totals.cache_creation_1h_tokens,
+            cache_read_tokens: month.totals.cache_read_tokens,
+            total_tokens: month.totals.total_tokens,
+            cost_usd: month.totals.cost_usd,
+            sidechain: month.totals.sidechain,
+            pricing_source: month.totals.pricing_source,
+            long_context_cost_usd: month.totals.long_context_cost_usd,
+            long_context_requests: month.totals.long_context_requests,
+        })
+        .collect()
+}
+
+/// Aggregate entries per project directory, or per recorded working directory
+///
+/// Entries without a recorded working directory are attributed to their decoded
+/// project directory. A project's path is the working directory most of its cost
+/// was recorded in.
+pub fn aggregate_entries_by_project(mut entries: Vec<ProcessedEntry>, by_cwd: bool) -> Vec<ProjectStats> {
+    // Decoding consults the filesystem, so do it once per project directory
+    let mut decoded_paths: HashMap<String, String> = HashMap::new();
+    for entry in entries.iter_mut().filter(|entry| entry.cwd.is_empty()) {
+        let path = decoded_paths
+            .entry(entry.project.clone())
+            .or_insert_with(|| decode_project_dir_name(&entry.project).display().to_string());
+        entry.cwd = path.clone();
+    }
+    
+    let dimensions: &[GroupBy] = if by_cwd { &[GroupBy::Cwd] } else { &[GroupBy::Project, GroupBy::Cwd] };
+    let mut project_stats: Vec<ProjectStats> = group_entries(&entries, dimensions)
+        .into_iter()
+        .map(|group| {
+            // Working directories are ordered by cost, highest first
+            let (project, path) = match group.groups.first() {
+                Some(cwd) if !by_cwd => (group.raw_key, cwd.key.clone()),
+                _ => (group.key.clone(), group.key),
+            };
+            ProjectStats {
+                project,
+                path,
+                models: group.totals.models,
+                input_tokens: group.totals.input_tokens,
+                output_tokens: group.totals.output_tokens,
+                cache_creation_tokens: group.totals.cache_creation_tokens,
+                cache_read_tokens: group.totals.cache_read_tokens,
+                total_tokens: group.totals.total_tokens,
+                cost_usd: group.totals.cost_usd,
+                session_count: group.totals.session_count,
+                sidechain: group.totals.sidechain,
+                pricing_source: group.totals.pricing_source,
+                long_context_cost_usd: group.totals.long_context_cost_usd,
+                // Every group holds at least one entry
+                first_activity: group.totals.first_seen.unwrap_or_default(),
+                last_activity: group.totals.last_seen.unwrap_or_default(),
+            }
+        })
+        .collect();
+    
+    project_stats.sort_by(|a, b| a.project.cmp(&b.project));
+    project_stats
+}
+
+/// Aggregate entries per Claude Code version, oldest release first
+pub fn aggregate_entries_by_version(entries: Vec<ProcessedEntry>) -> Vec<VersionStats> {
+    group_entries(&entries, &[GroupBy::Version])
+        .into_iter()
+        .map(|version| VersionStats {
+            version: version.key,
+            entry_count: version.totals.entry_count,
+            input_tokens: version.totals.input_tokens,
+            output_tokens: version.totals.output_tokens,
+            cache_creation_tokens: version.totals.cache_creation_tokens,
+            cache_read_tokens: version.totals.cache_read_tokens,
+            total_tokens: version.totals.total_tokens,
+            cost_usd: version.totals.cost_usd,
+            pricing_source: version.totals.pricing_source,
+            long_context_cost_usd: version.totals.long_context_cost_usd,
+            // Every group holds at least one entry
+            first_seen: version.totals.first_seen.unwrap_or_default(),
+            last_seen: version.totals.last_seen.unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Claude project directory name for a session file (its parent directory)
+pub fn project_name_for_file(file_path: &Path) -> String {
+    file_path
+        .parent()
+        .and_then(|dir| dir.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+pub fn total_tokens(usage: &Usage) -> u64 {
+    usage.input_tokens + usage.output_tokens + usage.cache_creation_input_tokens + usage.cache_read_input_tokens
 }
 
-fn simplify_model_name(model: &str) -> String {
-    if model.contains("opus") {
-        "opus-4".to_string()
-    } else if model.contains("sonnet") {
-        "sonnet-4".to_string()
-    } else if model.contains("haiku") {
-        "haiku".to_string()
-    } else {
-        // Extract first two parts separated by dashes
-        model.split('-').take(2).collect::<Vec<_>>().join("-")
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use chrono::TimeZone;
+
+    fn entry(project: &str, session_id: &str, hour: u32, cost: f64) -> ProcessedEntry {
+        let timestamp = Local.with_ymd_and_hms(2025, 6, 1, hour, 0, 0).unwrap();
+        ProcessedEntry {
+            date: timestamp.format("%Y-%m-%d").to_string(),
+            timestamp,
+            model: "claude-sonnet-4-20250514".to_string(),
+            usage: Usage {
+                input_tokens: 10,
+                output_tokens: 20,
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: 0,
+                service_tier: None,
+                cache_creation: None,
+            },
+            cost,
+            project: project.to_string(),
+            cwd: String::new(),
+            session_id: session_id.to_string(),
+            is_sidechain: false,
+            version: "1.0.30".to_string(),
+            pricing_source: PricingSource::Known,
+            long_context: false,
+        }
+    }
+
+    #[test]
+    fn test_parallel_loading_matches_sequential_deduplication() {
+        let dir = TempDir::new("parallel");
+        let project = dir.join("-home-me-repo");
+        std::fs::create_dir_all(&project).unwrap();
+
+        // Every file repeats some message/request pairs of the previous ones
+        let mut files = Vec::new();
+        for file_index in 0..16u32 {
+            let path = project.join(format!("session-{:02}.jsonl", file_index));
+            let lines: Vec<String> = (0..20u32)
+                .map(|i| {
+                    let id = file_index * 10 + i;
+                    format!(
+                        r#"{{"sessionId":"s{}","timestamp":"2025-06-01T{:02}:{:02}:00Z","requestId":"r{}","message":{{"id":"m{}","model":"claude-sonnet-4-20250514","usage":{{"input_tokens":{},"output_tokens":{}}}}}}}"#,
+                        file_index, i % 24, file_index, id, id, id, file_index + 1
+                    )
+                })
+                .collect();
+            std::fs::write(&path, lines.join("\n")).unwrap();
+            files.push(path);
+        }
+
+        let mut hashes = HashSet::new();
+        let mut sequential = Vec::new();
+        for file in &files {
+            for record in parse_file_records(file).unwrap().records {
+                if claim_unique(&record, &mut hashes) && record.billable {
+                    sequential.push(processed_entry(&record));
+                }
+            }
+        }
+
+        let parallel = load_all_entries(&files, &DataSourceOptions::default()).unwrap();
+
+        assert_eq!(format!("{:?}", parallel), format!("{:?}", sequential));
+        assert_eq!(parallel.len(), 16 * 10 + 10);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_malformed_lines_are_skipped_unless_strict() {
+        let dir = TempDir::new("strict");
+        let project = dir.join("-home-me-repo");
+        std::fs::create_dir_all(&project).unwrap();
+        let file = project.join("session.jsonl");
+        std::fs::write(
+            &file,
+            [
+                r#"{"type":"summary","summary":"Refactor","leafUuid":"u1"}"#,
+                r#"{"timestamp":"2025-06-01T10:00:00Z","requestId":"r1","message":{"id":"m1","model":"claude-sonnet-4-20250514","usage":{"input_tokens":10}}}"#,
+                r#"{"timestamp":"not a time","requestId":"r2","message":{"id":"m2","model":"claude-sonnet-4-20250514","usage":{"input_tokens":20}}}"#,
+                r#"{"timestamp":"2025-06-01T10:05:00Z","requestId":"r3","mess"#,
+                r#"{"timestamp":"2025-06-01T10:10:00Z","requestId":"r4","message":{"id":"m4","model":"claude-sonnet-4-20250514","usage":{"input_tokens":40}}}"#,
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let parsed = parse_file_records(&file).unwrap();
+        assert_eq!(parsed.malformed.iter().map(|issue| issue.line).collect::<Vec<_>>(), vec![3, 4]);
+
+        let lenient = load_all_entries(std::slice::from_ref(&file), &DataSourceOptions::default()).unwrap();
+        assert_eq!(lenient.iter().map(|entry| entry.usage.input_tokens).sum::<u64>(), 50);
+
+        let strict = DataSourceOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let error = load_all_entries(std::slice::from_ref(&file), &strict).unwrap_err();
+        assert!(error.to_string().contains("session.jsonl:3: invalid timestamp"));
+    }
+
+    #[test]
+    fn test_sessions_are_grouped_by_session_id_across_files() {
+        let dir = TempDir::new("sessions");
+        let project = dir.join("-home-me-repo");
+        std::fs::create_dir_all(&project).unwrap();
+
+        let usage = |session: &str, minute: u32, request: &str, tokens: u32| {
+            format!(
+                r#"{{"sessionId":"{}","timestamp":"2025-06-01T10:{:02}:00Z","requestId":"{}","message":{{"id":"m-{}","model":"claude-sonnet-4-20250514","usage":{{"input_tokens":{}}}}}}}"#,
+                session, minute, request, request, tokens
+            )
+        };
+        let first = project.join("first.jsonl");
+        let second = project.join("second.jsonl");
+        std::fs::write(&first, [usage("a", 0, "r1", 10), usage("b", 5, "r2", 20)].join("\n")).unwrap();
+        std::fs::write(
+            &second,
+            [
+                // Resumed session: a copied line plus new usage, then a later user message
+                usage("a", 0, "r1", 10),
+                usage("a", 30, "r3", 30),
+                r#"{"sessionId":"a","timestamp":"2025-06-01T10:45:00Z","type":"user","message":{"role":"user","content":"thanks"}}"#.to_string(),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let mut sessions = load_sessions(&[first, second], &DataSourceOptions::default()).unwrap();
+        sessions.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+
+        assert_eq!(sessions.len(), 2);
+        let a = &sessions[0];
+        assert_eq!(a.model_usage["claude-sonnet-4-20250514"].total_input, 40);
+        assert_eq!(a.start_time.format("%H:%M").to_string(), "10:00");
+        assert_eq!(a.end_time.unwrap().format("%H:%M").to_string(), "10:45");
+        assert_eq!(sessions[1].model_usage["claude-sonnet-4-20250514"].total_input, 20);
+    }
+
+    #[test]
+    fn test_sidechain_usage_is_split_per_day_project_and_session() {
+        let dir = TempDir::new("sidechain");
+        let project = dir.join("-home-me-repo");
+        std::fs::create_dir_all(&project).unwrap();
+        let file = project.join("session.jsonl");
+
+        let usage = |request: &str, sidechain: bool, tokens: u32| {
+            format!(
+                r#"{{"sessionId":"s1","isSidechain":{},"timestamp":"2025-06-01T10:00:00Z","requestId":"{}","message":{{"id":"m-{}","model":"claude-sonnet-4-20250514","usage":{{"input_tokens":{}}}}}}}"#,
+                sidechain, request, request, tokens
+            )
+        };
+        std::fs::write(&file, [usage("r1", false, 100), usage("r2", true, 300), usage("r3", true, 600)].join("\n")).unwrap();
+        let files = std::slice::from_ref(&file);
+
+        let daily = process_all_entries(files, &DataSourceOptions::default()).unwrap();
+        assert_eq!(daily[0].sidechain.main_tokens, 100);
+        assert_eq!(daily[0].sidechain.sidechain_tokens, 900);
+        assert!((daily[0].sidechain.sidechain_cost_share() - 0.9).abs() < 1e-9);
+
+        let entries = load_all_entries(files, &DataSourceOptions::default()).unwrap();
+        let projects = aggregate_entries_by_project(entries, false);
+        assert_eq!(projects[0].sidechain.sidechain_tokens, 900);
+
+        let sessions = load_sessions(files, &DataSourceOptions::default()).unwrap();
+        assert_eq!(sessions[0].sidechain.main_tokens, 100);
+        assert_eq!(sessions[0].sidechain.sidechain_tokens, 900);
+    }
+
+    #[test]
+    fn test_aggregate_entries_by_version() {
+        let mut entries = vec![
+            entry("-home-me-alpha", "s1", 9, 1.0),
+            entry("-home-me-alpha", "s1", 10, 2.0),
+            entry("-home-me-alpha", "s2", 11, 4.0),
+        ];
+        entries[1].version = "1.0.9".to_string();
+        entries[2].version = "1.0.9".to_string();
+        entries[2].usage.cache_read_input_tokens = 60;
+
+        let stats = aggregate_entries_by_version(entries);
+
+        assert_eq!(stats.iter().map(|stat| stat.version.as_str()).collect::<Vec<_>>(), vec!["1.0.9", "1.0.30"]);
+        assert_eq!(stats[0].entry_count, 2);
+        assert_eq!(stats[0].cost_usd, 6.0);
+        assert_eq!(stats[0].cache_hit_ratio(), 0.75);
+        assert_eq!(stats[1].cache_hit_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_only_calculated_costs_count_as_long_context_spend() {
+        let record = |cost_usd| UsageRecord {
+            timestamp: Utc.with_ymd_and_hms(2025, 6, 1, 10, 0, 0).unwrap(),
+            model: "claude-sonnet-4-20250514".to_string(),
+            usage: Usage {
+                input_tokens: 300_000,
+                ..Default::default()
+            },
+            cost_usd,
+            version: "1.0.30".to_string(),
+            ..Default::default()
+        };
+
+        // A recorded cost was never priced at the premium rates
+        let entries = || vec![processed_entry(&record(None)), processed_entry(&record(Some(1.0)))];
+        assert!(entries()[0].long_context);
+        assert!(!entries()[1].long_context);
+
+        let stats = aggregate_entries_by_version(entries());
+        assert!((stats[0].long_context_cost_usd - 1.8).abs() < 1e-9);
+        assert!((stats[0].cost_usd - 2.8).abs() < 1e-9);
+
+        let json = crate::commands::generate_monthly_json_output(&aggregate_entries_by_month(entries())).unwrap();
+        let month = &json["monthly"][0];
+        assert!((month["long_context_cost_usd"].as_f64().unwrap() - 1.8).abs() < 1e-9);
+        assert_eq!(month["long_context_requests"], 1);
+    }
+
+    #[test]
+    fn test_aggregate_entries_by_project() {
+        let entries = vec![
+            entry("-home-me-alpha", "s1", 9, 1.0),
+            entry("-home-me-alpha", "s1", 11, 2.0),
+            entry("-home-me-alpha", "s2", 10, 0.5),
+            entry("-home-me-beta", "s3", 12, 4.0),
+        ];
+
+        let stats = aggregate_entries_by_project(entries, false);
+
+        assert_eq!(stats.len(), 2);
+        let alpha = &stats[0];
+        assert_eq!(alpha.project, "-home-me-alpha");
+        assert_eq!(alpha.session_count, 2);
+        assert_eq!(alpha.total_tokens, 90);
+        assert_eq!(alpha.cost_usd, 3.5);
+        assert_eq!(alpha.first_activity.format("%H").to_string(), "09");
+        assert_eq!(alpha.last_activity.format("%H").to_string(), "11");
+
+        // Working directories group across project directories, falling back to the decoded one
+        let mut entries = vec![
+            entry("-home-me-alpha", "s1", 9, 1.0),
+            entry("-home-me-alpha", "s2", 10, 2.0),
+            entry("-home-me-beta", "s3", 12, 4.0),
+        ];
+        entries[0].cwd = "/work/alpha".to_string();
+        entries[2].cwd = "/work/alpha".to_string();
+
+        let stats = aggregate_entries_by_project(entries, true);
+
+        assert_eq!(stats.iter().map(|stat| stat.path.as_str()).collect::<Vec<_>>(), vec!["/home/me/alpha", "/work/alpha"]);
+        assert_eq!(stats[1].cost_usd, 5.0);
+        assert_eq!(stats[1].session_count, 2);
+    }
+}
@@ -4,24 +4,28 @@
 //!
 //! ## Key Components
 //! - [`SessionEntry`] - Represents a single JSONL entry
-//! - [`parse_session_file`] - Parse a complete session file
 //! - [`extract_model_usage`] - Extract model-specific token counts
+//! - [`CacheCreation`] - Split of cache writes by cache lifetime
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 
 use crate::models::calculate_weighted_tokens;
+use crate::pricing::{PricingSource, entry_cost};
+use crate::session_log;
+use crate::table_display::SidechainSplit;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionEntry {
     #[serde(default)]
     pub parent_uuid: Option<String>,
+    /// Parent across a compaction boundary, where `parentUuid` is null
+    #[serde(default)]
+    pub logical_parent_uuid: Option<String>,
     #[serde(default)]
     pub is_sidechain: bool,
     #[serde(default)]
@@ -66,7 +70,7 @@ pub struct Message {
     pub cost_usd: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Usage {
     #[serde(default)]
     pub input_tokens: u64,
@@ -78,6 +82,32 @@ pub struct Usage {
     pub cache_read_input_tokens: u64,
     #[serde(default)]
     pub service_tier: Option<String>,
+    /// Cache writes by lifetime, recorded by newer Claude Code versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation: Option<CacheCreation>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheCreation {
+    #[serde(default)]
+    pub ephemeral_5m_input_tokens: u64,
+    #[serde(default)]
+    pub ephemeral_1h_input_tokens: u64,
+}
+
+impl Usage {
+    /// Cache write tokens with a 1-hour lifetime; the rest of `cache_creation_input_tokens`
+    /// are 5-minute writes, which is all of them in logs without a split
+    pub fn cache_creation_1h_tokens(&self) -> u64 {
+        self.cache_creation
+            .as_ref()
+            .map_or(0, |split| split.ephemeral_1h_input_tokens.min(self.cache_creation_input_tokens))
+    }
+
+    /// Cache write tokens with a 5-minute lifetime
+    pub fn cache_creation_5m_tokens(&self) -> u64 {
+        self.cache_creation_input_tokens - self.cache_creation_1h_tokens()
+    }
 }
 
 #[derive(Debug, Default, Clone, Serialize)]
@@ -86,22 +116,38 @@ pub struct ModelUsage {
     pub total_input: u64,
     pub total_output: u64,
     pub total_cache_write: u64,
+    /// Part of `total_cache_write` with a 5-minute cache lifetime
+    pub total_cache_write_5m: u64,
+    /// Part of `total_cache_write` with a 1-hour cache lifetime
+    pub total_cache_write_1h: u64,
     pub total_cache_read: u64,
     pub message_count: u32,
     pub weighted_tokens: u64,
+    /// Cost of the requests, each priced under `--mode` when it was added
+    pub cost_usd: f64,
+    /// `fallback` or `zero` if the model has no known rates
+    pub pricing_source: PricingSource,
 }
 
 impl ModelUsage {
-    pub fn add_usage(&mut self, usage: &Usage) {
+    /// Add one request; `recorded_cost` is the `costUSD` of its log entry, used according to `--mode`
+    #[allow(dead_code)]
+    pub fn add_usage(&mut self, usage: &Usage, recorded_cost: Option<f64>, timestamp: DateTime<Utc>) {
         self.total_input += usage.input_tokens;
         self.total_output += usage.output_tokens;
         self.total_cache_write += usage.cache_creation_input_tokens;
+        self.total_cache_write_5m += usage.cache_creation_5m_tokens();
+        self.total_cache_write_1h += usage.cache_creation_1h_tokens();
         self.total_cache_read += usage.cache_read_input_tokens;
         self.message_count += 1;
 
         let raw_tokens = usage.input_tokens + usage.output_tokens;
         let weighted = calculate_weighted_tokens(&self.model_name, raw_tokens);
         self.weighted_tokens += weighted;
+
+        let (cost, pricing_source) = entry_cost(usage, &self.model_name, recorded_cost, timestamp);
+        self.cost_usd += cost;
+        self.pricing_source = self.pricing_source.combine(pricing_source);
     }
 
 }
@@ -113,8 +159,10 @@ pub struct SessionData {
     pub end_time: Option<DateTime<Utc>>,
     pub model_usage: HashMap<String, ModelUsage>,
     pub total_weighted_tokens: u64,
-    pub has_limit_error: bool,
-    pub _limit_type: Option<String>, // "opus" or "general"
+    /// Main-thread vs sidechain usage, filled in by the session report
+    pub sidechain: SidechainSplit,
+    /// Cost of requests billed at long-context (premium tier) rates, filled in by the session report
+    pub long_context_cost_usd: f64,
 }
 
 impl SessionData {
@@ -125,56 +173,9 @@ impl SessionData {
             end_time: None,
             model_usage: HashMap::new(),
             total_weighted_tokens: 0,
-            has_limit_error: false,
-            _limit_type: None,
-        }
-    }
-
-    pub fn add_entry(&mut self, entry: &SessionEntry) -> Result<()> {
-        if let Some(message) = &entry.message {
-            // Check for limit reached errors
-            if entry.is_api_error_message {
-                if let Some(content) = &message.content {
-                    if let Some(text) = content
-                        .as_array()
-                        .and_then(|arr| arr.first())
-                        .and_then(|v| v.get("text"))
-                        .and_then(|t| t.as_str())
-                    {
-                        if text.contains("Claude AI usage limit reached") {
-                            self.has_limit_error = true;
-                            // TODO: Parse limit type from error message
-                        }
-                    }
-                }
-            }
-
-            // Track token usage by model (matching ccusage filtering exactly)
-            if let (Some(model), Some(usage)) = (&message.model, &message.usage) {
-                // Only filter synthetic models (matching ccusage aggregateByModel behavior)
-                if model != "<synthetic>" {
-                    let model_usage =
-                        self.model_usage
-                            .entry(model.clone())
-                            .or_insert_with(|| ModelUsage {
-                                model_name: model.clone(),
-                                ..Default::default()
-                            });
-                    model_usage.add_usage(usage);
-                }
-            }
-        }
-
-        // Update session end time
-        let timestamp = DateTime::parse_from_rfc3339(&entry.timestamp)
-            .context("Failed to parse timestamp")?
-            .with_timezone(&Utc);
-
-        if self.end_time.is_none() || timestamp > self.end_time.unwrap() {
-            self.end_time = Some(timestamp);
+            sidechain: SidechainSplit::default(),
+            long_context_cost_usd: 0.0,
         }
-
-        Ok(())
     }
 
     pub fn calculate_totals(&mut self) {
@@ -190,53 +191,13 @@ impl SessionData {
 
 
 
-pub fn parse_session_file(path: &Path) -> Result<SessionData> {
-    let file = File::open(path).context("Failed to open JSONL file")?;
-    let reader = BufReader::new(file);
-
-    let mut session_data: Option<SessionData> = None;
-
-    for line in reader.lines() {
-        let line = line.context("Failed to read line")?;
-        if line.trim().is_empty() {
-            continue;
-        }
-
-        // Check if this is a summary entry (skip it)
-        if line.contains("\"type\":\"summary\"") {
-            continue;
-        }
-
-        // Try to parse as SessionEntry
-        match serde_json::from_str::<SessionEntry>(&line) {
-            Ok(entry) => {
-                // Initialize session data on first valid entry
-                if session_data.is_none() {
-                    if let Ok(timestamp) = DateTime::parse_from_rfc3339(&entry.timestamp) {
-                        session_data = Some(SessionData::new(
-                            entry.session_id.clone(),
-                            timestamp.with_timezone(&Utc),
-                        ));
-                    }
-                }
-
-                if let Some(ref mut data) = session_data {
-                    let _ = data.add_entry(&entry); // Ignore individual entry errors
-                }
-            }
-            Err(_) => {
-                // Skip entries that don't match our expected format
-                continue;
-            }
-        }
-    }
-
-    if let Some(mut data) = session_data {
-        data.calculate_totals();
-        Ok(data)
-    } else {
-        anyhow::bail!("No valid session entries found in JSONL file")
-    }
+/// Whether a line that is not a [`SessionEntry`] is still a well-formed record
+///
+/// Records such as `{"type":"summary",...}` carry no message and are expected to
+/// be skipped, unlike broken JSON or entries with fields of the wrong type.
+pub fn is_metadata_record(line: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(line)
+        .is_ok_and(|value| value.is_object() && value.get("message").is_none())
 }
 
 pub fn find_session_files(
@@ -253,7 +214,7 @@ pub fn find_session_files(
         let entry = entry?;
         let path = entry.path();
 
-        if path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
+        if session_log::is_session_log(&path) {
             if let Some(since_time) = since {
                 let metadata = entry.metadata()?;
                 let modified = metadata.modified()?;
@@ -274,22 +235,18 @@ pub fn find_session_files(
 }
 
 
-pub fn get_all_project_dirs(_cwd: &Path) -> Vec<PathBuf> {
-    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
-    let claude_dir = home.join(".claude").join("projects");
-
-    if !claude_dir.exists() {
-        return Vec::new();
-    }
-
+/// Return all Claude project directories under the given `projects` roots
+pub fn get_all_project_dirs(roots: &[PathBuf]) -> Vec<PathBuf> {
     let mut found_dirs = Vec::new();
 
     // Return ALL Claude project directories (matching ccusage behavior)
-    if let Ok(entries) = std::fs::read_dir(&claude_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_dir() {
-                found_dirs.push(path);
+    for root in roots {
+        if let Ok(entries) = std::fs::read_dir(root) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    found_dirs.push(path);
+                }
             }
         }
     }
@@ -316,7 +273,8 @@ mod tests {
             cache_creation_input_tokens: 50,
             cache_read_input_tokens: 25,
             service_tier: None,
-        });
+            cache_creation: None,
+        }, None, Utc::now());
 
         assert_eq!(usage.total_input + usage.total_output, 300);
         assert_eq!(usage.weighted_tokens, 1500); // 300 * 5.0 multiplier
@@ -3,28 +3,53 @@
 //! Real-time token usage monitoring for Claude
 //!
 //! ## Key Components
+//! - [`aggregation`] - Nested grouping of usage by arbitrary dimensions
+//! - [`cache`] - Persistent cache of parsed session data
 //! - [`cli`] - Command-line interface definitions and argument parsing
-//! - [`commands`] - Command handlers for daily, monthly, session operations  
+//! - [`commands`] - Command handlers for daily, monthly, session, projects operations  
+//! - [`conversation_tree`] - Conversation branches rebuilt from message parent links
 //! - [`data_processing`] - Data filtering, sorting, and aggregation utilities
+//! - [`data_sources`] - Claude data root resolution and session file discovery
+//! - [`doctor`] - Diagnostics over raw session files
 //! - [`monitor`] - Real-time monitoring functionality
+//! - [`session_log`] - Reading plain, compressed and archived session logs
+//! - [`tail_reader`] - Incremental ingestion of appended session data
+//! - [`tool_usage`] - Usage attributed to the tools each assistant turn called
+//! - [`watcher`] - Filesystem notifications driving the monitor
 
+mod aggregation;
 mod block_builder;
+mod cache;
 mod cli;
 mod commands;
+mod conversation_tree;
 mod data_processing;
+mod data_sources;
+mod doctor;
 mod entry_processor;
 mod jsonl_parser;
 mod models;
 mod monitor;
 mod pricing;
+mod session_log;
 mod table_display;
+mod tail_reader;
+#[cfg(test)]
+mod test_support;
+mod tool_usage;
+mod watcher;
 
 use anyhow::Result;
 use clap::Parser;
 use log::debug;
 
 use cli::{Args, Commands, SortOrder};
-use commands::{handle_daily_command, handle_monthly_command, handle_session_command};
+use data_sources::DataSourceOptions;
+use commands::{
+    handle_branches_command, handle_cache_command, handle_daily_command, handle_doctor_command, handle_monthly_command,
+    handle_projects_command, handle_report_command, handle_session_command, handle_tools_command,
+    handle_versions_command,
+};
 use monitor::handle_monitor_command;
 
 #[tokio::main]
@@ -43,74 +68,57 @@ async fn main() -> Result<()> {
             .init();
     }
 
-    // Handle test parser legacy option
-    if args.test_parser {
-        return test_parser_comparison();
+    let sources = DataSourceOptions::from_args(&args)?;
+    // `cache` and `doctor` must keep working when the pricing file is broken
+    let pricing = pricing::load_pricing(args.pricing.as_deref(), args.offline);
+    match (&args.command, pricing) {
+        (Some(Commands::Cache { .. } | Commands::Doctor { .. }), Err(e)) => {
+            eprintln!("Warning: {:#}", e);
+        }
+        (_, pricing) => pricing?,
     }
+    pricing::set_unknown_model_policy(args.unknown_model);
+    pricing::set_cost_mode(args.mode);
 
     // Route to appropriate command handler
-    match args.command {
+    let result = match args.command {
         Some(Commands::Daily { since, until, order, json, breakdown, recent }) => {
-            handle_daily_command(since.as_deref(), until.as_deref(), order, json, breakdown, recent)
+            handle_daily_command(&sources, since.as_deref(), until.as_deref(), order, json, breakdown, recent)
         }
         Some(Commands::Monthly { since, until, order, json, breakdown }) => {
-            handle_monthly_command(since.as_deref(), until.as_deref(), order, json, breakdown)
+            handle_monthly_command(&sources, since.as_deref(), until.as_deref(), order, json, breakdown)
         }
         Some(Commands::Session { since, until, order, json, breakdown, recent }) => {
-            handle_session_command(since.as_deref(), until.as_deref(), order, json, breakdown, recent)
+            handle_session_command(&sources, since.as_deref(), until.as_deref(), order, json, breakdown, recent)
+        }
+        Some(Commands::Projects { since, until, order, json, by_cwd, breakdown }) => {
+            handle_projects_command(&sources, since.as_deref(), until.as_deref(), order, json, by_cwd, breakdown)
+        }
+        Some(Commands::Report { group_by, since, until, order, json }) => {
+            handle_report_command(&sources, &group_by, since.as_deref(), until.as_deref(), order, json)
+        }
+        Some(Commands::Versions { since, until, order, json }) => {
+            handle_versions_command(&sources, since.as_deref(), until.as_deref(), order, json)
+        }
+        Some(Commands::Branches { since, until, order, json, session }) => {
+            handle_branches_command(&sources, since.as_deref(), until.as_deref(), order, json, session.as_deref())
         }
+        Some(Commands::Tools { since, until, sort_by, order, by_server, json }) => {
+            handle_tools_command(&sources, since.as_deref(), until.as_deref(), sort_by, order, by_server, json)
+        }
+        Some(Commands::Doctor { json }) => handle_doctor_command(&sources, json),
+        Some(Commands::Cache { action }) => handle_cache_command(action),
         Some(Commands::Monitor { plan, reset_hour, timezone, active, recent, refresh_interval }) => {
-            handle_monitor_command(plan, reset_hour, timezone, active, recent, refresh_interval).await
+            handle_monitor_command(&sources, plan, reset_hour, timezone, active, recent, refresh_interval).await
         }
         None => {
             // Default to daily command for backward compatibility
-            handle_daily_command(None, None, SortOrder::Desc, false, false, None)
+            handle_daily_command(&sources, None, None, SortOrder::Desc, false, None, None)
         }
-    }
-}
+    };
 
-/// Test parser comparison (legacy functionality)
-fn test_parser_comparison() -> Result<()> {
-    println!("🧪 Testing JSONL parser compatibility...");
-    
-    // Get current working directory for project lookup
-    let cwd = std::env::current_dir()?;
-    let project_dirs = jsonl_parser::get_all_project_dirs(&cwd);
-
-    if project_dirs.is_empty() {
-        anyhow::bail!(
-            "No Claude session data found. Make sure you're in a project directory that has been used with Claude Code."
-        );
+    if result.is_ok() {
+        pricing::warn_unknown_models();
     }
-
-    // Find all JSONL session files from all project directories
-    let mut session_files = Vec::new();
-    for project_dir in &project_dirs {
-        let files = jsonl_parser::find_session_files(project_dir, None)?;
-        session_files.extend(files);
-    }
-
-    if session_files.is_empty() {
-        anyhow::bail!(
-            "No JSONL session files found in project directories. This project may not have any Claude Code usage yet."
-        );
-    }
-
-    println!("✅ Found {} session files", session_files.len());
-
-    // Test entry processor
-    let daily_stats = entry_processor::process_all_entries(&session_files)?;
-    println!("✅ Processed {} daily statistics", daily_stats.len());
-
-    // Test session parser
-    let mut total_sessions = 0;
-    for file in &session_files {
-        let _sessions = jsonl_parser::parse_session_file(file)?;
-        total_sessions += 1; // parse_session_file returns a single SessionData, not Vec
-    }
-    println!("✅ Parsed {} sessions", total_sessions);
-
-    println!("🎉 Parser test completed successfully!");
-    
-    Ok(())
-}
\ No newline at end of file
+    result
+}
@@ -4,7 +4,8 @@
 //!
 //! ## Key Components
 //! - [`handle_monitor_command`] - Main monitoring command handler
-//! - [`run_monitor`] - Core monitoring loop
+//! - [`run_monitor`] - Core monitoring loop (incremental, via [`IncrementalSessions`])
+//!   driven by filesystem events from [`SessionWatcher`], with polling as a fallback
 //! - [`validate_monitor_config`] - Configuration validation
 //! - Display utilities for real-time updates
 
@@ -21,9 +22,14 @@ use std::time::Duration as StdDuration;
 use tokio::{signal, time::sleep};
 
 use crate::cli::Plan;
+use crate::data_sources::DataSourceOptions;
+use crate::jsonl_parser::SessionData;
+use crate::tail_reader::IncrementalSessions;
+use crate::watcher::{SessionFileSet, SessionWatcher};
 use crate::block_builder::{Block as NativeBlock, build_blocks_from_sessions};
 
 /// Helper function to format numbers with thousands separators
+#[allow(clippy::manual_is_multiple_of)]
 fn format_number(n: u64) -> String {
     let mut result = String::new();
     let s = n.to_string();
@@ -102,6 +108,7 @@ pub struct Block {
 
 /// Handle monitor command with real-time updates
 pub async fn handle_monitor_command(
+    sources: &DataSourceOptions,
     plan: Plan,
     reset_hour: Option<u32>,
     timezone: String,
@@ -111,6 +118,9 @@ pub async fn handle_monitor_command(
 ) -> Result<()> {
     // Validate monitor configuration
     validate_monitor_config(reset_hour, &timezone)?;
+    if !sources.inputs.is_empty() {
+        anyhow::bail!("--input is not supported by monitor, which follows the Claude directories live");
+    }
     
     // Setup terminal - don't use raw mode as it interferes with output
     let mut stdout = io::stdout();
@@ -119,7 +129,7 @@ pub async fn handle_monitor_command(
     execute!(stdout, Clear(ClearType::All), Hide)?;
 
     // Ensure we restore terminal on exit
-    let result = run_monitor(plan, reset_hour, timezone, active_only, recent_blocks, refresh_interval).await;
+    let result = run_monitor(sources, plan, reset_hour, timezone, active_only, recent_blocks, refresh_interval).await;
 
     // Restore terminal
     execute!(stdout, Show)?;
@@ -133,97 +143,113 @@ pub async fn handle_monitor_command(
 }
 
 /// Main monitoring loop
-pub async fn run_monitor(plan: Plan, _reset_hour: Option<u32>, _timezone: String, active_only: bool, recent_blocks: Option<usize>, refresh_interval: u64) -> Result<()> {
+///
+/// Redraws as soon as a watched session file changes. Where filesystem events
+/// are unavailable it falls back to rescanning every `refresh_interval` seconds.
+pub async fn run_monitor(sources: &DataSourceOptions, plan: Plan, _reset_hour: Option<u32>, _timezone: String, active_only: bool, recent_blocks: Option<usize>, refresh_interval: u64) -> Result<()> {
     let mut stdout = io::stdout();
+    let mut sessions = IncrementalSessions::new(sources);
+    let mut files = SessionFileSet::discover(sources);
+    sessions.refresh(files.files())?;
+
+    let mut watcher = match SessionWatcher::start(&sources.roots()) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            log::debug!("Filesystem events unavailable, polling every {}s: {}", refresh_interval, e);
+            None
+        }
+    };
     
     loop {
         // Clear screen and move to top
         execute!(stdout, Clear(ClearType::All), MoveTo(0, 0))?;
-        
-        // Get current working directory for project lookup
-        let cwd = std::env::current_dir().context("Failed to get current directory")?;
-        let project_dirs = crate::jsonl_parser::get_all_project_dirs(&cwd);
+        render(&files, &sessions, plan, active_only, recent_blocks);
+        stdout.flush()?;
 
-        if project_dirs.is_empty() {
-            println!("❌ No Claude session data found.");
-            println!("   Make sure you're in a project directory that has been used with Claude Code.");
+        let Some(active_watcher) = &mut watcher else {
+            // Polling: rescan everything after each interval
             tokio::select! {
-                _ = sleep(StdDuration::from_secs(5)) => continue,
+                _ = sleep(StdDuration::from_secs(refresh_interval)) => {},
                 _ = signal::ctrl_c() => break,
             }
-        }
+            files = SessionFileSet::discover(sources);
+            sessions.refresh(files.files())?;
+            continue;
+        };
 
-        // Find all JSONL session files from all project directories
-        let mut session_files = Vec::new();
-        for project_dir in &project_dirs {
-            if let Ok(files) = crate::jsonl_parser::find_session_files(project_dir, None) {
-                session_files.extend(files);
-            }
+        // Watching: wake on changes, still redrawing periodically so block timings stay current
+        tokio::select! {
+            batch = active_watcher.next_batch() => match batch {
+                Some(batch) if batch.rescan => {
+                    files = SessionFileSet::discover(sources);
+                    sessions.refresh(files.files())?;
+                }
+                Some(batch) => {
+                    let changed = files.apply_changes(sources, &batch.paths);
+                    sessions.update(&changed)?;
+                }
+                None => {
+                    log::debug!("Filesystem watcher stopped, falling back to polling");
+                    watcher = None;
+                }
+            },
+            _ = sleep(StdDuration::from_secs(refresh_interval)) => {},
+            _ = signal::ctrl_c() => break,
         }
+    }
 
-        if session_files.is_empty() {
-            println!("❌ No JSONL session files found.");
-            println!("   This project may not have any Claude Code usage yet.");
-            tokio::select! {
-                _ = sleep(StdDuration::from_secs(5)) => continue,
-                _ = signal::ctrl_c() => break,
-            }
-        }
+    Ok(())
+}
 
-        // Parse all session files to get sessions
-        let mut all_sessions = Vec::new();
-        for file in &session_files {
-            if let Ok(session_data) = crate::jsonl_parser::parse_session_file(file) {
-                all_sessions.push(session_data);
-            }
-        }
+/// Draw one frame of the monitor from the current session aggregates
+fn render(files: &SessionFileSet, sessions: &IncrementalSessions, plan: Plan, active_only: bool, recent_blocks: Option<usize>) {
+    if files.project_dirs().is_empty() {
+        println!("❌ No Claude session data found.");
+        println!("   Make sure you're in a project directory that has been used with Claude Code.");
+        return;
+    }
 
-        if all_sessions.is_empty() {
-            println!("❌ No valid session data found.");
-            println!("   The JSONL files may be corrupted or in an unexpected format.");
-            tokio::select! {
-                _ = sleep(StdDuration::from_secs(5)) => continue,
-                _ = signal::ctrl_c() => break,
-            }
-        }
+    if files.files().is_empty() {
+        println!("❌ No JSONL session files found.");
+        println!("   This project may not have any Claude Code usage yet.");
+        return;
+    }
 
-        // Build blocks from sessions
-        if let Ok(native_blocks) = build_blocks_from_sessions(&all_sessions) {
-            let mut blocks: Vec<Block> = native_blocks.into_iter().map(convert_native_block).collect();
-            
-            // Apply filtering
-            if active_only {
-                blocks.retain(|block| block.is_active);
-            }
-            
-            if let Some(recent_count) = recent_blocks {
-                // Sort by start time (most recent first) and keep only recent blocks
-                blocks.sort_by(|a, b| b.start_time.cmp(&a.start_time));
-                blocks.truncate(recent_count);
-            }
-            
-            // Display monitoring interface
-            print_header();
-            
-            let token_limit = get_token_limit(plan, Some(&blocks));
-            display_blocks(&blocks, token_limit);
-        } else {
-            println!("❌ Failed to build blocks from sessions.");
+    let all_sessions: Vec<&SessionData> = sessions.sessions().collect();
+
+    if all_sessions.is_empty() {
+        println!("❌ No valid session data found.");
+        println!("   The JSONL files may be corrupted or in an unexpected format.");
+        return;
+    }
+
+    // Build blocks from sessions
+    if let Ok(native_blocks) = build_blocks_from_sessions(all_sessions) {
+        let mut blocks: Vec<Block> = native_blocks.into_iter().map(convert_native_block).collect();
+        
+        // Apply filtering
+        if active_only {
+            blocks.retain(|block| block.is_active);
         }
         
-        stdout.flush()?;
-
-        // Wait for update interval or Ctrl+C
-        tokio::select! {
-            _ = sleep(StdDuration::from_secs(refresh_interval)) => {},
-            _ = signal::ctrl_c() => break,
+        if let Some(recent_count) = recent_blocks {
+            // Sort by start time (most recent first) and keep only recent blocks
+            blocks.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+            blocks.truncate(recent_count);
         }
+        
+        // Display monitoring interface
+        print_header();
+        
+        let token_limit = get_token_limit(plan, Some(&blocks));
+        display_blocks(&blocks, token_limit);
+    } else {
+        println!("❌ Failed to build blocks from sessions.");
     }
-
-    Ok(())
 }
 
 /// Validate monitor configuration
+#[allow(clippy::collapsible_if)]
 pub fn validate_monitor_config(reset_hour: Option<u32>, timezone: &str) -> Result<()> {
     // Validate reset hour
     if let Some(hour) = reset_hour {
@@ -1,36 +1,568 @@
 //! # Pricing Engine Module
 //!
-//! Handles cost calculations for Claude API usage based on current Anthropic pricing
+//! Handles cost calculations for Claude API usage based on current Anthropic pricing,
+//! optionally overridden by a user pricing file
 //!
 //! ## Key Components
 //! - [`ModelPricing`] - Pricing structure for different token types
+//! - [`LongContextPricing`] - Premium rates for requests above a prompt-size threshold
+//! - [`load_pricing`] - Install prices from a pricing file, consulted before the built-in table
+//! - [`PricingTable`] - Model patterns and their prices, parsed from TOML or JSON
 //! - [`calculate_session_cost`] - Calculate total cost for a session
-//! - [`get_model_pricing`] - Get pricing configuration for a specific model
+//! - [`service_tier_multiplier`] - Price multiplier for the service tier a request ran on
+//! - [`get_model_pricing_at`] - Pricing in effect for a model at a given time
+//! - [`PricingSource`] - Whether usage was priced from known rates, fallback rates or as free
+//! - [`check_unknown_models`] / [`warn_unknown_models`] - Apply `--unknown-model` to unpriced models
+//! - [`entry_cost`] - Cost of one request under `--mode`, used by every report and the monitor
 
-use std::collections::HashMap;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::cache::cache_dir;
+use crate::cli::{CostMode, UnknownModelPolicy};
 use crate::jsonl_parser::{ModelUsage, Usage};
 
+/// Message Batches are billed at 50% of standard prices
+/// (https://docs.anthropic.com/en/docs/build-with-claude/batch-processing#pricing)
+const BATCH_TIER_MULTIPLIER: f64 = 0.5;
+/// 1-hour cache writes cost twice the input price unless a pricing file sets `cache_write_1h`
+const CACHE_WRITE_1H_MULTIPLIER: f64 = 2.0;
+
+/// Copy of the last pricing file loaded, used by `--offline`
+const CACHED_PRICING_FILE_NAME: &str = "pricing.json";
+
+/// Prices from the user's pricing file, if one was loaded
+static PRICING_TABLE: OnceLock<PricingTable> = OnceLock::new();
+
+/// `--unknown-model`, `warn` unless set
+static UNKNOWN_MODEL_POLICY: OnceLock<UnknownModelPolicy> = OnceLock::new();
+
+/// `--mode`, `auto` unless set
+static COST_MODE: OnceLock<CostMode> = OnceLock::new();
+
+/// Models that had to be priced without known rates during this run
+static UNKNOWN_MODELS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Service tiers that had to be priced at standard rates during this run
+static UNKNOWN_TIERS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// How the cost of some usage was determined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PricingSource {
+    /// Cost recorded in the log, or rates from the pricing file or built-in table
+    #[default]
+    Known,
+    /// Model without known rates, priced at fallback (Sonnet) rates, or service
+    /// tier without a known multiplier, priced at standard rates
+    Fallback,
+    /// Model without known rates, counted as free (`--unknown-model zero`)
+    Zero,
+}
+
+impl PricingSource {
+    /// Source of combined usage: any unknown pricing taints the total
+    pub fn combine(self, other: PricingSource) -> PricingSource {
+        if self == PricingSource::Known { other } else { self }
+    }
+
+    /// Suffix marking costs that are not based on known rates
+    pub fn marker(self) -> &'static str {
+        if self == PricingSource::Known { "" } else { "*" }
+    }
+}
+
+/// Contents of a pricing file
+///
+/// ```toml
+/// [[models]]
+/// model = "claude-opus-4-1*"
+/// aliases = ["claude-4-1-opus*"]
+/// input = 15.0        # USD per million tokens
+/// output = 75.0
+/// cache_write = 18.75 # optional 5-minute cache write price, defaults to 1.25x input
+/// cache_write_1h = 30.0 # optional 1-hour cache write price, defaults to 2x input
+/// cache_read = 1.5    # optional, defaults to 0.1x input
+/// effective_from = 2025-08-05  # optional, first day these prices apply
+/// effective_until = 2025-12-01 # optional, first day they no longer apply
+///
+/// # optional, rates for every token of requests whose prompt exceeds the threshold
+/// long_context = { threshold = 200000, input = 6.0, output = 22.5 }
+///
+/// # optional, multipliers of the model prices per `usage.service_tier`
+/// [service_tiers]
+/// priority = 1.25
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PricingFile {
+    #[serde(default)]
+    pub models: Vec<PricingFileEntry>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub service_tiers: HashMap<String, f64>,
+}
+
+/// Prices of the models matching one name or glob pattern, in USD per million tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricingFileEntry {
+    pub model: String,
+    /// Further names or patterns priced the same way
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub input: f64,
+    pub output: f64,
+    #[serde(default)]
+    pub cache_write: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write_1h: Option<f64>,
+    #[serde(default)]
+    pub cache_read: Option<f64>,
+    /// First day (UTC) the prices apply
+    #[serde(default, deserialize_with = "deserialize_price_date", skip_serializing_if = "Option::is_none")]
+    pub effective_from: Option<NaiveDate>,
+    /// First day (UTC) the prices no longer apply
+    #[serde(default, deserialize_with = "deserialize_price_date", skip_serializing_if = "Option::is_none")]
+    pub effective_until: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_context: Option<LongContextEntry>,
+}
+
+/// Premium prices of a pricing file entry, in USD per million tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LongContextEntry {
+    /// Prompt tokens (input, cache creation and cache read) above which these prices apply
+    pub threshold: u64,
+    pub input: f64,
+    pub output: f64,
+    #[serde(default)]
+    pub cache_write: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write_1h: Option<f64>,
+    #[serde(default)]
+    pub cache_read: Option<f64>,
+}
+
+/// Accept a `YYYY-MM-DD` string, or a TOML date literal
+fn deserialize_price_date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDate>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum DateValue {
+        Text(String),
+        Toml(toml::value::Datetime),
+    }
+
+    let text = match Option::<DateValue>::deserialize(deserializer)? {
+        None => return Ok(None),
+        Some(DateValue::Text(text)) => text,
+        Some(DateValue::Toml(datetime)) => datetime.to_string(),
+    };
+    NaiveDate::parse_from_str(&text, "%Y-%m-%d")
+        .map(Some)
+        .map_err(|e| serde::de::Error::custom(format!("invalid date '{}': {}", text, e)))
+}
+
+/// Compiled pricing file: the first entry with a matching pattern, in effect at
+/// the time of use, prices a model
+#[derive(Debug, Clone)]
+pub struct PricingTable {
+    file: PricingFile,
+    rules: Vec<PricingRule>,
+}
+
+#[derive(Debug, Clone)]
+struct PricingRule {
+    patterns: Vec<glob::Pattern>,
+    effective_from: Option<NaiveDate>,
+    effective_until: Option<NaiveDate>,
+    pricing: ModelPricing,
+}
+
+impl PricingRule {
+    fn applies(&self, model_name: &str, date: NaiveDate) -> bool {
+        self.effective_from.is_none_or(|from| date >= from)
+            && self.effective_until.is_none_or(|until| date < until)
+            && self.patterns.iter().any(|pattern| pattern.matches(model_name))
+    }
+}
+
+impl PricingTable {
+    /// Parse a pricing file, as JSON if `json` is set and TOML otherwise
+    pub fn parse(contents: &str, json: bool) -> Result<Self> {
+        let file: PricingFile = if json {
+            serde_json::from_str(contents).context("Invalid JSON pricing file")?
+        } else {
+            toml::from_str(contents).context("Invalid TOML pricing file")?
+        };
+        Self::from_file(file)
+    }
+
+    fn from_file(file: PricingFile) -> Result<Self> {
+        if let Some((tier, _)) = file.service_tiers.iter().find(|(_, multiplier)| !multiplier.is_finite() || **multiplier < 0.0) {
+            anyhow::bail!("Service tier '{}' has a negative or invalid multiplier", tier);
+        }
+
+        let mut rules = Vec::new();
+        for entry in &file.models {
+            let mut rates = vec![Some(entry.input), Some(entry.output), entry.cache_write, entry.cache_write_1h, entry.cache_read];
+            if let Some(tier) = &entry.long_context {
+                rates.extend([Some(tier.input), Some(tier.output), tier.cache_write, tier.cache_write_1h, tier.cache_read]);
+            }
+            if rates.into_iter().flatten().any(|rate| !rate.is_finite() || rate < 0.0) {
+                anyhow::bail!("Pricing for '{}' has a negative or invalid rate", entry.model);
+            }
+            if let (Some(from), Some(until)) = (entry.effective_from, entry.effective_until)
+                && from >= until
+            {
+                anyhow::bail!("Pricing for '{}' has effective_from on or after effective_until", entry.model);
+            }
+
+            let patterns = std::iter::once(&entry.model)
+                .chain(&entry.aliases)
+                .map(|pattern| {
+                    glob::Pattern::new(pattern).with_context(|| format!("Invalid model pattern '{}'", pattern))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let pricing = ModelPricing {
+                input_cost_per_token: entry.input / 1e6,
+                output_cost_per_token: entry.output / 1e6,
+                cache_creation_input_token_cost: entry.cache_write.unwrap_or(entry.input * 1.25) / 1e6,
+                cache_creation_1h_input_token_cost: entry.cache_write_1h.unwrap_or(entry.input * CACHE_WRITE_1H_MULTIPLIER) / 1e6,
+                cache_read_input_token_cost: entry.cache_read.unwrap_or(entry.input * 0.1) / 1e6,
+                long_context: entry.long_context.as_ref().map(|tier| LongContextPricing {
+                    threshold_tokens: tier.threshold,
+                    input_cost_per_token: tier.input / 1e6,
+                    output_cost_per_token: tier.output / 1e6,
+                    cache_creation_input_token_cost: tier.cache_write.unwrap_or(tier.input * 1.25) / 1e6,
+                    cache_creation_1h_input_token_cost: tier.cache_write_1h.unwrap_or(tier.input * CACHE_WRITE_1H_MULTIPLIER) / 1e6,
+                    cache_read_input_token_cost: tier.cache_read.unwrap_or(tier.input * 0.1) / 1e6,
+                }),
+            };
+            rules.push(PricingRule {
+                patterns,
+                effective_from: entry.effective_from,
+                effective_until: entry.effective_until,
+                pricing,
+            });
+        }
+        Ok(Self { file, rules })
+    }
+
+    /// Prices of the first entry whose model name or an alias matches, in effect at `at`
+    pub fn lookup(&self, model_name: &str, at: DateTime<Utc>) -> Option<&ModelPricing> {
+        let date = at.date_naive();
+        self.rules.iter().find(|rule| rule.applies(model_name, date)).map(|rule| &rule.pricing)
+    }
+
+    /// Multiplier the file sets for a service tier
+    pub fn tier_multiplier(&self, service_tier: &str) -> Option<f64> {
+        self.file.service_tiers.get(service_tier).copied()
+    }
+}
+
+/// Install the user's prices, consulted before the built-in table
+///
+/// Reads `path`, or else `pricing.toml` / `pricing.json` in the ccusage-rs
+/// config directory, and keeps a copy in the cache directory. With `offline`
+/// no pricing file is read and only that cached copy is used, if any. The copy
+/// is only replaced by reading another pricing file, or removed by `cache clear`.
+pub fn load_pricing(path: Option<&Path>, offline: bool) -> Result<()> {
+    let path = path.map(Path::to_path_buf).or_else(default_pricing_file);
+    let cached = cache_dir().map(|dir| dir.join(CACHED_PRICING_FILE_NAME));
+
+    if let Some(table) = resolve_pricing(path.as_deref(), cached.as_deref(), offline)? {
+        install_pricing(table);
+    }
+    Ok(())
+}
+
+/// Make `table` the prices consulted before the built-in table; only the first call has an effect
+pub fn install_pricing(table: PricingTable) {
+    log::debug!("Loaded prices for {} model pattern(s)", table.rules.len());
+    let _ = PRICING_TABLE.set(table);
+}
+
+/// Pricing file to use: `path` unless `offline`, copied to `cached`, or else the
+/// cached copy when `offline`
+fn resolve_pricing(path: Option<&Path>, cached: Option<&Path>, offline: bool) -> Result<Option<PricingTable>> {
+    if offline {
+        return match cached.filter(|cached| cached.exists()) {
+            Some(cached) => Ok(Some(read_pricing_file(cached)?)),
+            None => Ok(None),
+        };
+    }
+
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let table = read_pricing_file(path)?;
+    if let Some(cached) = cached
+        && let Err(e) = save_pricing_copy(&table, cached)
+    {
+        log::debug!("Failed to cache pricing file: {}", e);
+    }
+    Ok(Some(table))
+}
+
+/// `pricing.toml` or `pricing.json` in the ccusage-rs config directory, if present
+fn default_pricing_file() -> Option<PathBuf> {
+    let dir = dirs::config_dir()?.join("ccusage-rs");
+    ["pricing.toml", "pricing.json"].into_iter().map(|name| dir.join(name)).find(|path| path.exists())
+}
+
+fn read_pricing_file(path: &Path) -> Result<PricingTable> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read pricing file {}", path.display()))?;
+    let json = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    PricingTable::parse(&contents, json).with_context(|| format!("Failed to load pricing file {}", path.display()))
+}
+
+fn save_pricing_copy(table: &PricingTable, location: &Path) -> Result<()> {
+    let dir = location.parent().context("Invalid cache location")?;
+    std::fs::create_dir_all(dir).context("Failed to create cache directory")?;
+    std::fs::write(location, serde_json::to_vec_pretty(&table.file)?).context("Failed to write pricing copy")?;
+    Ok(())
+}
+
+/// Prompt size above which Sonnet 4 bills the 1M-context premium rates
+const SONNET_4_LONG_CONTEXT_THRESHOLD: u64 = 200_000;
 
 #[derive(Debug, Clone)]
 pub struct ModelPricing {
+    pub input_cost_per_token: f64,
+    pub output_cost_per_token: f64,
+    /// 5-minute cache write rate
+    pub cache_creation_input_token_cost: f64,
+    /// 1-hour cache write rate
+    pub cache_creation_1h_input_token_cost: f64,
+    pub cache_read_input_token_cost: f64,
+    /// Premium rates for requests with long prompts, if the model has them
+    pub long_context: Option<LongContextPricing>,
+}
+
+/// Rates billed for every token of a request whose prompt exceeds the threshold
+#[derive(Debug, Clone)]
+pub struct LongContextPricing {
+    /// Prompt tokens (input, cache creation and cache read) above which the rates apply
+    pub threshold_tokens: u64,
     pub input_cost_per_token: f64,
     pub output_cost_per_token: f64,
     pub cache_creation_input_token_cost: f64,
+    pub cache_creation_1h_input_token_cost: f64,
     pub cache_read_input_token_cost: f64,
 }
 
 impl ModelPricing {
-    pub fn calculate_cost(&self, usage: &ModelUsage) -> f64 {
-        let input_cost = (usage.total_input as f64) * self.input_cost_per_token;
-        let output_cost = (usage.total_output as f64) * self.output_cost_per_token;
-        let cache_creation_cost = (usage.total_cache_write as f64) * self.cache_creation_input_token_cost;
-        let cache_read_cost = (usage.total_cache_read as f64) * self.cache_read_input_token_cost;
-        
-        input_cost + output_cost + cache_creation_cost + cache_read_cost
+    /// Cost of one request, at the long-context rates if its prompt exceeds the threshold
+    pub fn request_cost(&self, usage: &Usage) -> f64 {
+        match &self.long_context {
+            Some(tier) if prompt_tokens(usage) > tier.threshold_tokens => token_cost(
+                usage,
+                [
+                    tier.input_cost_per_token,
+                    tier.output_cost_per_token,
+                    tier.cache_creation_input_token_cost,
+                    tier.cache_creation_1h_input_token_cost,
+                    tier.cache_read_input_token_cost,
+                ],
+            ),
+            _ => self.base_cost(usage),
+        }
+    }
+
+    /// Cost of the tokens at the base rates, ignoring any long-context tier
+    pub fn base_cost(&self, usage: &Usage) -> f64 {
+        token_cost(
+            usage,
+            [
+                self.input_cost_per_token,
+                self.output_cost_per_token,
+                self.cache_creation_input_token_cost,
+                self.cache_creation_1h_input_token_cost,
+                self.cache_read_input_token_cost,
+            ],
+        )
+    }
+
+    /// Whether a request is billed at the long-context rates
+    pub fn is_long_context(&self, usage: &Usage) -> bool {
+        self.long_context.as_ref().is_some_and(|tier| prompt_tokens(usage) > tier.threshold_tokens)
+    }
+}
+
+/// Set whether recorded or calculated costs are reported (`--mode`)
+pub fn set_cost_mode(mode: CostMode) {
+    let _ = COST_MODE.set(mode);
+}
+
+/// Cost of one request, and how it was priced, under the selected `--mode`
+pub fn entry_cost(usage: &Usage, model_name: &str, recorded_cost: Option<f64>, at: DateTime<Utc>) -> (f64, PricingSource) {
+    entry_cost_in_mode(cost_mode(), usage, model_name, recorded_cost, at)
+}
+
+/// Whether the selected `--mode` calculates a request's cost rather than using `recorded_cost`
+pub fn calculates_cost(recorded_cost: Option<f64>) -> bool {
+    calculates_cost_in_mode(cost_mode(), recorded_cost)
+}
+
+fn cost_mode() -> CostMode {
+    COST_MODE.get().copied().unwrap_or(CostMode::Auto)
+}
+
+fn calculates_cost_in_mode(mode: CostMode, recorded_cost: Option<f64>) -> bool {
+    match (mode, recorded_cost) {
+        (CostMode::Display, _) | (CostMode::Auto, Some(_)) => false,
+        (CostMode::Calculate, _) | (CostMode::Auto, None) => true,
+    }
+}
+
+fn entry_cost_in_mode(
+    mode: CostMode,
+    usage: &Usage,
+    model_name: &str,
+    recorded_cost: Option<f64>,
+    at: DateTime<Utc>,
+) -> (f64, PricingSource) {
+    if !calculates_cost_in_mode(mode, recorded_cost) {
+        return (recorded_cost.unwrap_or(0.0), PricingSource::Known);
+    }
+
+    let mut pricing_source = pricing_source_at(model_name, at);
+    if pricing_source != PricingSource::Known {
+        record_unknown_model(model_name);
+    }
+    if let Some(tier) = usage.service_tier.as_deref()
+        && service_tier_multiplier(Some(tier)).is_none()
+    {
+        record_unknown_tier(tier);
+        pricing_source = pricing_source.combine(PricingSource::Fallback);
+    }
+    (calculate_cost_from_tokens(usage, model_name, at), pricing_source)
+}
+
+/// Set how models without known rates are priced (`--unknown-model`)
+pub fn set_unknown_model_policy(policy: UnknownModelPolicy) {
+    let _ = UNKNOWN_MODEL_POLICY.set(policy);
+}
+
+fn unknown_model_policy() -> UnknownModelPolicy {
+    UNKNOWN_MODEL_POLICY.get().copied().unwrap_or(UnknownModelPolicy::Warn)
+}
+
+/// Fail if models or service tiers without known rates were priced and `--unknown-model error` is set
+pub fn check_unknown_models() -> Result<()> {
+    if unknown_model_policy() != UnknownModelPolicy::Error {
+        return Ok(());
+    }
+    let models = unknown_models();
+    if !models.is_empty() {
+        anyhow::bail!(
+            "No pricing for model(s): {}. Add them to a pricing file (see --pricing) or choose --unknown-model warn|zero|fallback",
+            models.join(", ")
+        );
+    }
+    let tiers = unknown_tiers();
+    if !tiers.is_empty() {
+        anyhow::bail!(
+            "No pricing for service tier(s): {}. Add them to `service_tiers` in a pricing file (see --pricing) or choose --unknown-model warn|zero|fallback",
+            tiers.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Print which models and service tiers were priced without known rates, unless `--unknown-model` silences it
+pub fn warn_unknown_models() {
+    if unknown_model_policy() != UnknownModelPolicy::Warn {
+        return;
+    }
+    let models = unknown_models();
+    if !models.is_empty() {
+        eprintln!(
+            "Warning: No pricing for model(s) {}; costs marked * use fallback Sonnet rates and may be understated. \
+             Add them to a pricing file (see --pricing) or choose --unknown-model error|zero|fallback",
+            models.join(", ")
+        );
+    }
+    let tiers = unknown_tiers();
+    if !tiers.is_empty() {
+        eprintln!(
+            "Warning: No pricing for service tier(s) {}; costs marked * use standard rates. \
+             Add them to `service_tiers` in a pricing file (see --pricing)",
+            tiers.join(", ")
+        );
+    }
+}
+
+/// Remember a model whose cost was calculated without known rates
+fn record_unknown_model(model_name: &str) {
+    if let Ok(mut models) = UNKNOWN_MODELS.lock() {
+        models.insert(model_name.to_string());
     }
 }
 
-pub fn get_model_pricing(model_name: &str) -> Option<ModelPricing> {
+fn unknown_models() -> Vec<String> {
+    UNKNOWN_MODELS.lock().map(|models| models.iter().cloned().collect()).unwrap_or_default()
+}
+
+/// Remember a service tier whose cost was calculated without a known multiplier
+fn record_unknown_tier(service_tier: &str) {
+    if let Ok(mut tiers) = UNKNOWN_TIERS.lock() {
+        tiers.insert(service_tier.to_string());
+    }
+}
+
+fn unknown_tiers() -> Vec<String> {
+    UNKNOWN_TIERS.lock().map(|tiers| tiers.iter().cloned().collect()).unwrap_or_default()
+}
+
+/// How usage of a model at `at` is priced, without an embedded cost
+pub fn pricing_source_at(model_name: &str, at: DateTime<Utc>) -> PricingSource {
+    if configured_model_pricing(model_name, at).is_some() || known_model_pricing(model_name).is_some() {
+        PricingSource::Known
+    } else if unknown_model_policy() == UnknownModelPolicy::Zero {
+        PricingSource::Zero
+    } else {
+        PricingSource::Fallback
+    }
+}
+
+/// Pricing in effect at `at`, so past usage keeps the prices it was billed at
+pub fn get_model_pricing_at(model_name: &str, at: DateTime<Utc>) -> Option<ModelPricing> {
+    if let Some(pricing) = configured_model_pricing(model_name, at).or_else(|| known_model_pricing(model_name)) {
+        return Some(pricing);
+    }
+
+    if unknown_model_policy() == UnknownModelPolicy::Zero {
+        return Some(ModelPricing {
+            input_cost_per_token: 0.0,
+            output_cost_per_token: 0.0,
+            cache_creation_input_token_cost: 0.0,
+            cache_creation_1h_input_token_cost: 0.0,
+            cache_read_input_token_cost: 0.0,
+            long_context: None,
+        });
+    }
+
+    // Default fallback for unknown models - use Sonnet 3.5 pricing
+    Some(ModelPricing {
+        input_cost_per_token: 3e-6,
+        output_cost_per_token: 15e-6,
+        cache_creation_input_token_cost: 3.75e-6,
+        cache_creation_1h_input_token_cost: 6e-6,
+        cache_read_input_token_cost: 0.3e-6,
+        long_context: None,
+    })
+}
+
+/// Pricing from the user's pricing file, if one was loaded and matches
+fn configured_model_pricing(model_name: &str, at: DateTime<Utc>) -> Option<ModelPricing> {
+    PRICING_TABLE.get()?.lookup(model_name, at).cloned()
+}
+
+fn known_model_pricing(model_name: &str) -> Option<ModelPricing> {
     // Official Anthropic API pricing as of June 2025
     // Prices are per million tokens - source: https://www.anthropic.com/pricing
     match model_name {
@@ -40,7 +572,9 @@ pub fn get_model_pricing(model_name: &str) -> Option<ModelPricing> {
                 input_cost_per_token: 3e-6,
                 output_cost_per_token: 15e-6,
                 cache_creation_input_token_cost: 3.75e-6,
+                cache_creation_1h_input_token_cost: 6e-6,
                 cache_read_input_token_cost: 0.3e-6,
+                long_context: None,
             })
         },
         // Claude 3.5 Haiku (Official Anthropic pricing)
@@ -49,7 +583,9 @@ pub fn get_model_pricing(model_name: &str) -> Option<ModelPricing> {
                 input_cost_per_token: 0.8e-6,
                 output_cost_per_token: 4e-6,
                 cache_creation_input_token_cost: 1e-6,
+                cache_creation_1h_input_token_cost: 1.6e-6,
                 cache_read_input_token_cost: 0.08e-6,
+                long_context: None,
             })
         },
         // Claude 3 Opus
@@ -58,7 +594,9 @@ pub fn get_model_pricing(model_name: &str) -> Option<ModelPricing> {
                 input_cost_per_token: 15e-6,
                 output_cost_per_token: 75e-6,
                 cache_creation_input_token_cost: 18.75e-6,
+                cache_creation_1h_input_token_cost: 30e-6,
                 cache_read_input_token_cost: 1.5e-6,
+                long_context: None,
             })
         },
         // Claude 3 Sonnet (legacy)
@@ -67,7 +605,9 @@ pub fn get_model_pricing(model_name: &str) -> Option<ModelPricing> {
                 input_cost_per_token: 3e-6,
                 output_cost_per_token: 15e-6,
                 cache_creation_input_token_cost: 3.75e-6,
+                cache_creation_1h_input_token_cost: 6e-6,
                 cache_read_input_token_cost: 0.3e-6,
+                long_context: None,
             })
         },
         // Claude 3 Haiku (legacy)
@@ -76,7 +616,9 @@ pub fn get_model_pricing(model_name: &str) -> Option<ModelPricing> {
                 input_cost_per_token: 0.25e-6,
                 output_cost_per_token: 1.25e-6,
                 cache_creation_input_token_cost: 0.31e-6,
+                cache_creation_1h_input_token_cost: 0.5e-6,
                 cache_read_input_token_cost: 0.025e-6,
+                long_context: None,
             })
         },
         // Claude 4 Opus (Official Anthropic pricing)
@@ -85,7 +627,9 @@ pub fn get_model_pricing(model_name: &str) -> Option<ModelPricing> {
                 input_cost_per_token: 15e-6,
                 output_cost_per_token: 75e-6,
                 cache_creation_input_token_cost: 18.75e-6,
+                cache_creation_1h_input_token_cost: 30e-6,
                 cache_read_input_token_cost: 1.5e-6,
+                long_context: None,
             })
         },
         // Claude 4 Sonnet (Official Anthropic pricing)
@@ -94,43 +638,78 @@ pub fn get_model_pricing(model_name: &str) -> Option<ModelPricing> {
                 input_cost_per_token: 3e-6,
                 output_cost_per_token: 15e-6,
                 cache_creation_input_token_cost: 3.75e-6,
+                cache_creation_1h_input_token_cost: 6e-6,
                 cache_read_input_token_cost: 0.3e-6,
+                long_context: Some(LongContextPricing {
+                    threshold_tokens: SONNET_4_LONG_CONTEXT_THRESHOLD,
+                    input_cost_per_token: 6e-6,
+                    output_cost_per_token: 22.5e-6,
+                    cache_creation_input_token_cost: 7.5e-6,
+                    cache_creation_1h_input_token_cost: 12e-6,
+                    cache_read_input_token_cost: 0.6e-6,
+                }),
             })
         },
-        // Default fallback for unknown models - use Sonnet 3.5 pricing
-        _ => {
-            Some(ModelPricing {
-                input_cost_per_token: 3e-6,
-                output_cost_per_token: 15e-6,
-                cache_creation_input_token_cost: 3.75e-6,
-                cache_read_input_token_cost: 0.3e-6,
-            })
-        }
+        _ => None,
     }
 }
 
+/// Total cost of a session: the sum of each request's cost, priced as it was added
 pub fn calculate_session_cost(model_usage: &HashMap<String, ModelUsage>) -> f64 {
-    model_usage.iter()
-        .filter_map(|(model_name, usage)| {
-            get_model_pricing(model_name).map(|pricing| pricing.calculate_cost(usage))
-        })
-        .sum()
-}
-
-/// Calculate cost from token usage and model name
-pub fn calculate_cost_from_tokens(usage: &Usage, model_name: &str) -> f64 {
-    if let Some(pricing) = get_model_pricing(model_name) {
-        let input_cost = (usage.input_tokens as f64) * pricing.input_cost_per_token;
-        let output_cost = (usage.output_tokens as f64) * pricing.output_cost_per_token;
-        let cache_write_cost = (usage.cache_creation_input_tokens as f64) * pricing.cache_creation_input_token_cost;
-        let cache_read_cost = (usage.cache_read_input_tokens as f64) * pricing.cache_read_input_token_cost;
-        
-        input_cost + output_cost + cache_write_cost + cache_read_cost
-    } else {
-        0.0
+    model_usage.values().map(|usage| usage.cost_usd).sum()
+}
+
+/// Price multiplier for a `usage.service_tier` value, `None` for a tier without known pricing
+pub fn service_tier_multiplier(service_tier: Option<&str>) -> Option<f64> {
+    tier_multiplier_in(PRICING_TABLE.get(), service_tier)
+}
+
+/// Multiplier from the pricing file, else built in: entries without a tier and
+/// `standard` pay list price, `batch` half
+fn tier_multiplier_in(table: Option<&PricingTable>, service_tier: Option<&str>) -> Option<f64> {
+    let Some(tier) = service_tier else {
+        return Some(1.0);
+    };
+    if let Some(multiplier) = table.and_then(|table| table.tier_multiplier(tier)) {
+        return Some(multiplier);
+    }
+    match tier {
+        "standard" => Some(1.0),
+        "batch" => Some(BATCH_TIER_MULTIPLIER),
+        _ => None,
     }
 }
 
+/// Calculate cost from token usage and model name at the prices in effect at `at`,
+/// including the service tier multiplier; tiers without known pricing pay standard prices
+pub fn calculate_cost_from_tokens(usage: &Usage, model_name: &str, at: DateTime<Utc>) -> f64 {
+    standard_cost_from_tokens(usage, model_name, at) * service_tier_multiplier(usage.service_tier.as_deref()).unwrap_or(1.0)
+}
+
+/// Cost of the tokens at standard tier prices in effect at `at`
+pub fn standard_cost_from_tokens(usage: &Usage, model_name: &str, at: DateTime<Utc>) -> f64 {
+    get_model_pricing_at(model_name, at).map_or(0.0, |pricing| pricing.request_cost(usage))
+}
+
+/// Whether a request was billed at its model's long-context rates
+pub fn is_long_context_request(usage: &Usage, model_name: &str, at: DateTime<Utc>) -> bool {
+    get_model_pricing_at(model_name, at).is_some_and(|pricing| pricing.is_long_context(usage))
+}
+
+fn prompt_tokens(usage: &Usage) -> u64 {
+    usage.input_tokens + usage.cache_creation_input_tokens + usage.cache_read_input_tokens
+}
+
+/// Cost of a request's tokens at `[input, output, 5-minute cache write, 1-hour cache write, cache read]`
+/// per-token rates
+fn token_cost(usage: &Usage, rates: [f64; 5]) -> f64 {
+    (usage.input_tokens as f64) * rates[0]
+        + (usage.output_tokens as f64) * rates[1]
+        + (usage.cache_creation_5m_tokens() as f64) * rates[2]
+        + (usage.cache_creation_1h_tokens() as f64) * rates[3]
+        + (usage.cache_read_input_tokens as f64) * rates[4]
+}
+
 pub fn calculate_cost_per_hour(total_cost: f64, duration_minutes: f64) -> f64 {
     if duration_minutes <= 0.0 {
         0.0
@@ -142,23 +721,22 @@ pub fn calculate_cost_per_hour(total_cost: f64, duration_minutes: f64) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::TempDir;
     use crate::jsonl_parser::ModelUsage;
 
     #[test]
     fn test_sonnet_pricing() {
-        let pricing = get_model_pricing("claude-3-5-sonnet-20241022").unwrap();
+        let pricing = get_model_pricing_at("claude-3-5-sonnet-20241022", Utc::now()).unwrap();
         
-        let usage = ModelUsage {
-            model_name: "claude-3-5-sonnet-20241022".to_string(),
-            total_input: 1_000_000,    // 1M input tokens
-            total_output: 500_000,     // 500K output tokens
-            total_cache_write: 100_000, // 100K cache write tokens
-            total_cache_read: 200_000,  // 200K cache read tokens
-            message_count: 10,
-            weighted_tokens: 1_500_000,
+        let usage = Usage {
+            input_tokens: 1_000_000,               // 1M input tokens
+            output_tokens: 500_000,                // 500K output tokens
+            cache_creation_input_tokens: 100_000, // 100K cache write tokens
+            cache_read_input_tokens: 200_000,     // 200K cache read tokens
+            ..Default::default()
         };
 
-        let cost = pricing.calculate_cost(&usage);
+        let cost = pricing.request_cost(&usage);
         
         // Expected: (1M * $3) + (500K * $15) + (100K * $3.75) + (200K * $0.30) = $3 + $7.5 + $0.375 + $0.06 = $10.935
         assert_eq!(cost, 10.935);
@@ -166,19 +744,15 @@ mod tests {
 
     #[test]
     fn test_opus_pricing() {
-        let pricing = get_model_pricing("claude-3-opus-20240229").unwrap();
+        let pricing = get_model_pricing_at("claude-3-opus-20240229", Utc::now()).unwrap();
         
-        let usage = ModelUsage {
-            model_name: "claude-3-opus-20240229".to_string(),
-            total_input: 100_000,    // 100K input tokens
-            total_output: 50_000,    // 50K output tokens
-            total_cache_write: 0,
-            total_cache_read: 0,
-            message_count: 5,
-            weighted_tokens: 750_000, // 5x multiplier
+        let usage = Usage {
+            input_tokens: 100_000, // 100K input tokens
+            output_tokens: 50_000, // 50K output tokens
+            ..Default::default()
         };
 
-        let cost = pricing.calculate_cost(&usage);
+        let cost = pricing.request_cost(&usage);
         
         // Expected: (100K * $15) + (50K * $75) = $1.5 + $3.75 = $5.25
         assert_eq!(cost, 5.25);
@@ -196,32 +770,319 @@ mod tests {
     #[test]
     fn test_session_cost_calculation() {
         let mut model_usage = HashMap::new();
+        let mut add = |model: &str, input_tokens: u64, output_tokens: u64| {
+            let usage = Usage {
+                input_tokens,
+                output_tokens,
+                ..Default::default()
+            };
+            model_usage
+                .entry(model.to_string())
+                .or_insert_with(|| ModelUsage {
+                    model_name: model.to_string(),
+                    ..Default::default()
+                })
+                .add_usage(&usage, None, Utc::now());
+        };
         
-        model_usage.insert("claude-3-5-sonnet-20241022".to_string(), ModelUsage {
-            model_name: "claude-3-5-sonnet-20241022".to_string(),
-            total_input: 500_000,
-            total_output: 250_000,
-            total_cache_write: 0,
-            total_cache_read: 0,
-            message_count: 5,
-            weighted_tokens: 750_000,
-        });
-        
-        model_usage.insert("claude-3-haiku-20240307".to_string(), ModelUsage {
-            model_name: "claude-3-haiku-20240307".to_string(),
-            total_input: 200_000,
-            total_output: 100_000,
-            total_cache_write: 0,
-            total_cache_read: 0,
-            message_count: 3,
-            weighted_tokens: 240_000,
-        });
+        add("claude-3-5-sonnet-20241022", 300_000, 150_000);
+        add("claude-3-5-sonnet-20241022", 200_000, 100_000);
+        add("claude-3-haiku-20240307", 200_000, 100_000);
 
         let total_cost = calculate_session_cost(&model_usage);
         
         // Sonnet: (500K * $3) + (250K * $15) = $1.5 + $3.75 = $5.25
         // Haiku: (200K * $0.25) + (100K * $1.25) = $0.05 + $0.125 = $0.175
         // Total: $5.25 + $0.175 = $5.425
-        assert_eq!(total_cost, 5.425);
+        assert!((total_cost - 5.425).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pricing_file_patterns_and_aliases() {
+        let table = PricingTable::parse(
+            r#"
+            [[models]]
+            model = "claude-opus-4-1*"
+            aliases = ["anthropic.claude-opus-4-1*"]
+            input = 15.0
+            output = 75.0
+            cache_write = 18.75
+            cache_read = 1.5
+
+            [[models]]
+            model = "claude-*"
+            input = 2.0
+            output = 10.0
+            "#,
+            false,
+        )
+        .unwrap();
+        let now = Utc::now();
+
+        let opus = table.lookup("claude-opus-4-1-20250805", now).unwrap();
+        assert_eq!(opus.output_cost_per_token, 75e-6);
+        assert_eq!(table.lookup("anthropic.claude-opus-4-1-20250805-v1:0", now).unwrap().input_cost_per_token, 15e-6);
+
+        // Earlier entries win; cache rates default from the input price
+        let other = table.lookup("claude-sonnet-4-20250514", now).unwrap();
+        assert_eq!(other.input_cost_per_token, 2e-6);
+        assert_eq!(other.cache_creation_input_token_cost, 2.5e-6);
+        assert!((other.cache_read_input_token_cost - 0.2e-6).abs() < 1e-15);
+        assert!(table.lookup("gpt-4o", now).is_none());
+
+        let json = PricingTable::parse(r#"{"models": [{"model": "x-*", "input": 1.0, "output": 2.0}]}"#, true).unwrap();
+        assert!(json.lookup("x-large", now).is_some());
+        assert!(PricingTable::parse(r#"{"models": [{"model": "x", "input": -1.0, "output": 2.0}]}"#, true).is_err());
+    }
+
+    #[test]
+    fn test_pricing_entries_apply_by_effective_date() {
+        let table = PricingTable::parse(
+            r#"
+            [[models]]
+            model = "claude-opus-4-1*"
+            input = 20.0
+            output = 100.0
+            effective_until = 2025-09-01
+
+            [[models]]
+            model = "claude-opus-4-1*"
+            input = 15.0
+            output = 75.0
+            effective_from = "2025-09-01"
+            "#,
+            false,
+        )
+        .unwrap();
+        let at = |date: &str| DateTime::parse_from_rfc3339(date).unwrap().with_timezone(&Utc);
+        let model = "claude-opus-4-1-20250805";
+
+        assert_eq!(table.lookup(model, at("2025-08-31T23:59:59Z")).unwrap().input_cost_per_token, 20e-6);
+        assert_eq!(table.lookup(model, at("2025-09-01T00:00:00Z")).unwrap().input_cost_per_token, 15e-6);
+
+        let reversed = r#"[[models]]
+            model = "x"
+            input = 1.0
+            output = 1.0
+            effective_from = 2025-09-01
+            effective_until = 2025-08-01"#;
+        assert!(PricingTable::parse(reversed, false).is_err());
+    }
+
+    #[test]
+    fn test_unknown_models_are_marked_as_fallback_priced() {
+        let now = Utc::now();
+        assert_eq!(pricing_source_at("claude-sonnet-4-20250514", now), PricingSource::Known);
+        assert_eq!(pricing_source_at("claude-mystery-9", now), PricingSource::Fallback);
+
+        // Only a calculated cost reports the model as unknown
+        let usage = Usage::default();
+        get_model_pricing_at("claude-mystery-recorded", now);
+        entry_cost_in_mode(CostMode::Auto, &usage, "claude-mystery-recorded", Some(1.0), now);
+        entry_cost_in_mode(CostMode::Display, &usage, "claude-mystery-recorded", None, now);
+        entry_cost_in_mode(CostMode::Auto, &usage, "claude-mystery-calculated", None, now);
+        let models = unknown_models();
+        assert!(!models.contains(&"claude-mystery-recorded".to_string()));
+        assert!(models.contains(&"claude-mystery-calculated".to_string()));
+
+        // Totals over several entries keep the marker once any entry has it
+        let combined = [PricingSource::Known, PricingSource::Fallback, PricingSource::Known]
+            .into_iter()
+            .fold(PricingSource::Known, PricingSource::combine);
+        assert_eq!(combined, PricingSource::Fallback);
+        assert_eq!(combined.marker(), "*");
+        assert_eq!(serde_json::to_value(combined).unwrap(), "fallback");
+    }
+
+    #[test]
+    fn test_cost_modes() {
+        let usage = Usage {
+            input_tokens: 100_000,
+            ..Default::default()
+        };
+        let model = "claude-sonnet-4-20250514";
+        let now = Utc::now();
+        let cost = |mode, recorded| entry_cost_in_mode(mode, &usage, model, recorded, now).0;
+
+        assert_eq!(cost(CostMode::Auto, Some(1.0)), 1.0);
+        assert_eq!(cost(CostMode::Auto, None), 0.3);
+        assert_eq!(cost(CostMode::Calculate, Some(1.0)), 0.3);
+        assert_eq!(cost(CostMode::Display, Some(1.0)), 1.0);
+        assert_eq!(cost(CostMode::Display, None), 0.0);
+
+        // Session totals follow the mode, since each request is priced as it is added
+        let mut model_usage = ModelUsage {
+            model_name: model.to_string(),
+            ..Default::default()
+        };
+        model_usage.add_usage(&usage, Some(1.0), now);
+        let session_cost = calculate_session_cost(&HashMap::from([(model.to_string(), model_usage)]));
+        assert!((session_cost - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_display_mode_never_prices_unknown_models() {
+        let usage = Usage {
+            input_tokens: 300_000,
+            ..Default::default()
+        };
+        let model = "claude-mystery-display";
+        let now = Utc::now();
+
+        assert_eq!(entry_cost_in_mode(CostMode::Display, &usage, model, None, now), (0.0, PricingSource::Known));
+        assert_eq!(entry_cost_in_mode(CostMode::Display, &usage, model, Some(2.0), now), (2.0, PricingSource::Known));
+        assert!(!calculates_cost_in_mode(CostMode::Display, None));
+        assert!(!unknown_models().contains(&model.to_string()));
+    }
+
+    #[test]
+    fn test_service_tier_pricing() {
+        let usage = |tier: Option<&str>| Usage {
+            input_tokens: 100_000,
+            output_tokens: 10_000,
+            service_tier: tier.map(str::to_string),
+            ..Default::default()
+        };
+        let model = "claude-sonnet-4-20250514";
+
+        // $0.30 input + $0.15 output at standard prices
+        let now = Utc::now();
+        let cost = |tier| calculate_cost_from_tokens(&usage(tier), model, now);
+        assert!((cost(None) - 0.45).abs() < 1e-9);
+        assert!((cost(Some("standard")) - 0.45).abs() < 1e-9);
+        assert!((cost(Some("batch")) - 0.225).abs() < 1e-9);
+
+        // A tier without a known multiplier pays standard prices and is marked as fallback
+        assert!((cost(Some("priority")) - 0.45).abs() < 1e-9);
+        let (_, source) = entry_cost_in_mode(CostMode::Calculate, &usage(Some("priority")), model, None, now);
+        assert_eq!(source, PricingSource::Fallback);
+        assert!(unknown_tiers().contains(&"priority".to_string()));
+        let (_, source) = entry_cost_in_mode(CostMode::Calculate, &usage(Some("batch")), model, None, now);
+        assert_eq!(source, PricingSource::Known);
+
+        // Session costs keep each request's tier
+        let mut model_usage = ModelUsage {
+            model_name: model.to_string(),
+            ..Default::default()
+        };
+        model_usage.add_usage(&usage(Some("batch")), None, Utc::now());
+        model_usage.add_usage(&usage(Some("standard")), None, Utc::now());
+        let session_cost = calculate_session_cost(&HashMap::from([(model.to_string(), model_usage)]));
+        assert!((session_cost - 0.675).abs() < 1e-9);
+
+        // A pricing file sets multipliers for other tiers and can override the built-in ones
+        let table = PricingTable::parse(
+            r#"[service_tiers]
+            priority = 1.25
+            batch = 0.4"#,
+            false,
+        )
+        .unwrap();
+        assert_eq!(tier_multiplier_in(Some(&table), Some("priority")), Some(1.25));
+        assert_eq!(tier_multiplier_in(Some(&table), Some("batch")), Some(0.4));
+        assert_eq!(tier_multiplier_in(Some(&table), Some("flex")), None);
+        assert!(PricingTable::parse("[service_tiers]\npriority = -1.0", false).is_err());
+    }
+
+    #[test]
+    fn test_long_context_pricing() {
+        let model = "claude-sonnet-4-20250514";
+        let now = Utc::now();
+        let short = Usage {
+            input_tokens: 150_000,
+            output_tokens: 10_000,
+            ..Default::default()
+        };
+        let long = Usage {
+            cache_read_input_tokens: 100_000,
+            ..short.clone()
+        };
+
+        // 150K input + 10K output at base rates
+        assert!((calculate_cost_from_tokens(&short, model, now) - 0.6).abs() < 1e-9);
+        assert!(!is_long_context_request(&short, model, now));
+
+        // A 250K-token prompt bills every token at the premium rates
+        assert!((calculate_cost_from_tokens(&long, model, now) - 1.185).abs() < 1e-9);
+        assert!(is_long_context_request(&long, model, now));
+
+        // Session costs keep each request's rates
+        let mut model_usage = ModelUsage {
+            model_name: model.to_string(),
+            ..Default::default()
+        };
+        model_usage.add_usage(&short, None, now);
+        model_usage.add_usage(&long, None, now);
+        let session_cost = calculate_session_cost(&HashMap::from([(model.to_string(), model_usage)]));
+        assert!((session_cost - 1.785).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cache_write_lifetimes() {
+        let model = "claude-sonnet-4-20250514";
+        let now = Utc::now();
+        let usage: Usage = serde_json::from_str(
+            r#"{"input_tokens": 0, "output_tokens": 0, "cache_creation_input_tokens": 100000,
+                "cache_creation": {"ephemeral_5m_input_tokens": 40000, "ephemeral_1h_input_tokens": 60000}}"#,
+        )
+        .unwrap();
+        assert_eq!((usage.cache_creation_5m_tokens(), usage.cache_creation_1h_tokens()), (40_000, 60_000));
+
+        // 40K at $3.75 and 60K at $6 (2x input) per million
+        assert!((calculate_cost_from_tokens(&usage, model, now) - 0.51).abs() < 1e-9);
+
+        // Without a split, every cache write is a 5-minute write
+        let unsplit = Usage {
+            cache_creation: None,
+            ..usage.clone()
+        };
+        assert!((calculate_cost_from_tokens(&unsplit, model, now) - 0.375).abs() < 1e-9);
+
+        let mut model_usage = ModelUsage {
+            model_name: model.to_string(),
+            ..Default::default()
+        };
+        model_usage.add_usage(&usage, None, now);
+        assert_eq!(model_usage.total_cache_write_1h, 60_000);
+        assert!((model_usage.cost_usd - 0.51).abs() < 1e-9);
+
+        // A pricing file can set the 1-hour rate, or leave it at 2x input
+        let table = PricingTable::parse(
+            r#"[[models]]
+            model = "claude-sonnet-4*"
+            input = 3.0
+            output = 15.0
+            cache_write_1h = 5.0
+
+            [[models]]
+            model = "claude-opus-4*"
+            input = 15.0
+            output = 75.0"#,
+            false,
+        )
+        .unwrap();
+        let sonnet = table.lookup(model, now).unwrap();
+        assert!((sonnet.request_cost(&usage) - 0.45).abs() < 1e-9);
+        let opus = table.lookup("claude-opus-4-20250514", now).unwrap();
+        assert!((opus.cache_creation_1h_input_token_cost - 30e-6).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_offline_runs_use_the_last_pricing_file_read() {
+        let dir = TempDir::new("pricing-offline");
+        let file = dir.join("x.toml");
+        std::fs::write(&file, "[[models]]\nmodel = \"claude-mystery*\"\ninput = 1.0\noutput = 2.0").unwrap();
+        let cached = dir.join("cache").join(CACHED_PRICING_FILE_NAME);
+        let now = Utc::now();
+
+        // `--pricing x.toml`, then a run without a pricing file, then `--offline`
+        assert!(resolve_pricing(Some(&file), Some(&cached), false).unwrap().is_some());
+        assert!(resolve_pricing(None, Some(&cached), false).unwrap().is_none());
+        let offline = resolve_pricing(None, Some(&cached), true).unwrap().unwrap();
+        assert!((offline.lookup("claude-mystery-1", now).unwrap().output_cost_per_token - 2e-6).abs() < 1e-15);
+
+        // Offline runs never read the pricing file itself
+        std::fs::remove_file(&cached).unwrap();
+        assert!(resolve_pricing(Some(&file), Some(&cached), true).unwrap().is_none());
     }
 }
\ No newline at end of file
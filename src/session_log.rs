@@ -0,0 +1,211 @@
+//! # Session Log Module
+//!
+//! Reading session logs however they are stored: plain JSONL, gzip/zstd-compressed
+//! JSONL, or tar archives of project directories
+//!
+//! ## Key Components
+//! - [`LogFormat`] - Storage format detected from the file name
+//! - [`is_session_log`] - Whether a file should be read as session data
+//! - [`for_each_stream`] - Visit every JSONL stream in a log file or archive
+//! - [`open_log`] - Line reader over a plain or compressed JSONL file
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Compression applied to a log file or archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// How a session log is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// A single session as JSONL (`.jsonl`, `.jsonl.gz`, `.jsonl.zst`)
+    Jsonl(Compression),
+    /// A tar archive of project directories (`.tar`, `.tar.gz`/`.tgz`, `.tar.zst`/`.tzst`)
+    Tar(Compression),
+}
+
+impl LogFormat {
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+
+        let format = if name.ends_with(".jsonl") {
+            Self::Jsonl(Compression::None)
+        } else if name.ends_with(".jsonl.gz") {
+            Self::Jsonl(Compression::Gzip)
+        } else if name.ends_with(".jsonl.zst") {
+            Self::Jsonl(Compression::Zstd)
+        } else if name.ends_with(".tar") {
+            Self::Tar(Compression::None)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Self::Tar(Compression::Gzip)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Self::Tar(Compression::Zstd)
+        } else {
+            return None;
+        };
+
+        Some(format)
+    }
+
+    pub fn is_archive(self) -> bool {
+        matches!(self, Self::Tar(_))
+    }
+}
+
+/// Whether the file is a session log in any supported format
+pub fn is_session_log(path: &Path) -> bool {
+    LogFormat::detect(path).is_some()
+}
+
+/// Whether the file is an archive that may hold several projects
+pub fn is_archive(path: &Path) -> bool {
+    LogFormat::detect(path).is_some_and(LogFormat::is_archive)
+}
+
+/// One JSONL stream: a log file itself or a member of an archive
+pub struct LogStream<'a> {
+    /// Path of the member inside the archive, `None` for a standalone file
+    pub member: Option<&'a Path>,
+    /// Claude project directory name the stream belongs to
+    pub project: String,
+    pub reader: &'a mut dyn BufRead,
+}
+
+/// Visit every JSONL stream stored in `path`
+///
+/// A plain or compressed file is a single stream. In an archive each JSONL
+/// member (itself optionally compressed) is a stream whose project is the
+/// directory containing it, or the archive's own directory for top-level members.
+pub fn for_each_stream<F>(path: &Path, mut visit: F) -> Result<()>
+where
+    F: FnMut(LogStream<'_>) -> Result<()>,
+{
+    let format = LogFormat::detect(path).unwrap_or(LogFormat::Jsonl(Compression::None));
+    let file_project = parent_dir_name(path).unwrap_or_default();
+
+    match format {
+        LogFormat::Jsonl(_) => {
+            let mut reader = open_log(path)?;
+            visit(LogStream {
+                member: None,
+                project: file_project,
+                reader: &mut reader,
+            })
+        }
+        LogFormat::Tar(compression) => {
+            let file = File::open(path).context("Failed to open archive")?;
+            let mut archive = tar::Archive::new(decompress(file, compression)?);
+
+            for entry in archive.entries().context("Failed to read archive")? {
+                let entry = entry.context("Failed to read archive entry")?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+
+                let member: PathBuf = entry.path().context("Invalid archive member path")?.into_owned();
+                let Some(LogFormat::Jsonl(member_compression)) = LogFormat::detect(&member) else {
+                    continue;
+                };
+
+                let mut reader = BufReader::new(decompress(entry, member_compression)?);
+                visit(LogStream {
+                    member: Some(&member),
+                    project: parent_dir_name(&member).unwrap_or_else(|| file_project.clone()),
+                    reader: &mut reader,
+                })
+                .with_context(|| format!("Failed to read archive member {}", member.display()))?;
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Open a plain or compressed JSONL file for line-by-line reading
+pub fn open_log(path: &Path) -> Result<Box<dyn BufRead>> {
+    let compression = match LogFormat::detect(path) {
+        Some(LogFormat::Jsonl(compression)) => compression,
+        Some(LogFormat::Tar(_)) => anyhow::bail!("{} is an archive, not a single log", path.display()),
+        None => Compression::None,
+    };
+
+    let file = File::open(path).context("Failed to open JSONL file")?;
+    Ok(Box::new(BufReader::new(decompress(file, compression)?)))
+}
+
+fn decompress<'a, R: Read + 'a>(reader: R, compression: Compression) -> Result<Box<dyn Read + 'a>> {
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(zstd::Decoder::new(reader).context("Failed to start zstd decoder")?),
+    })
+}
+
+fn parent_dir_name(path: &Path) -> Option<String> {
+    path.parent()
+        .and_then(|dir| dir.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::io::Write;
+
+    #[test]
+    fn test_detect_formats() {
+        assert_eq!(LogFormat::detect(Path::new("a.jsonl")), Some(LogFormat::Jsonl(Compression::None)));
+        assert_eq!(LogFormat::detect(Path::new("a.jsonl.gz")), Some(LogFormat::Jsonl(Compression::Gzip)));
+        assert_eq!(LogFormat::detect(Path::new("a.JSONL.ZST")), Some(LogFormat::Jsonl(Compression::Zstd)));
+        assert_eq!(LogFormat::detect(Path::new("2025-01.tgz")), Some(LogFormat::Tar(Compression::Gzip)));
+        assert_eq!(LogFormat::detect(Path::new("2025-01.tar.zst")), Some(LogFormat::Tar(Compression::Zstd)));
+        assert_eq!(LogFormat::detect(Path::new("notes.json")), None);
+    }
+
+    #[test]
+    fn test_streams_from_compressed_archive() {
+        let dir = TempDir::new("archive");
+        let archive_path = dir.join("old.tar.gz");
+
+        // A tar.gz holding a plain and a zstd-compressed session from two projects
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            File::create(&archive_path).unwrap(),
+            flate2::Compression::default(),
+        ));
+        let mut append = |name: &str, data: &[u8]| {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, data).unwrap();
+        };
+        append("projects/-home-me-alpha/s1.jsonl", b"one\ntwo\n");
+        append("projects/-home-me-beta/s2.jsonl.zst", &zstd::encode_all(&b"three\n"[..], 0).unwrap());
+        append("projects/-home-me-beta/notes.txt", b"ignored\n");
+        builder.into_inner().unwrap().finish().unwrap().flush().unwrap();
+
+        let mut streams = Vec::new();
+        for_each_stream(&archive_path, |stream| {
+            let lines: Vec<String> = stream.reader.lines().collect::<std::io::Result<_>>()?;
+            streams.push((stream.project, lines));
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(
+            streams,
+            vec![
+                ("-home-me-alpha".to_string(), vec!["one".to_string(), "two".to_string()]),
+                ("-home-me-beta".to_string(), vec!["three".to_string()]),
+            ]
+        );
+    }
+}
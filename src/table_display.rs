@@ -4,6 +4,7 @@
 //!
 //! ## Key Components
 //! - [`DailyStats`] - Daily aggregated statistics
+//! - [`SidechainSplit`] - Main-thread vs sidechain tokens and cost
 //! - [`format_table`] - Main table formatting function
 //! - [`format_number_compact`] - Compact number formatting for table cells
 
@@ -11,6 +12,8 @@ use anyhow::Result;
 use chrono::Datelike;
 use serde::Serialize;
 
+use crate::pricing::PricingSource;
+
 /// JSON structures matching ccusage format exactly
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -19,6 +22,8 @@ pub struct JsonModelBreakdown {
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub cache_creation_tokens: u64,
+    pub cache_creation_5m_tokens: u64,
+    pub cache_creation_1h_tokens: u64,
     pub cache_read_tokens: u64,
     pub cost: f64,
 }
@@ -35,6 +40,11 @@ pub struct JsonDailyEntry {
     pub total_cost: f64,
     pub models_used: Vec<String>,
     pub model_breakdowns: Vec<JsonModelBreakdown>,
+    /// Serialized like every other report's `sidechain` object
+    pub sidechain: SidechainSplit,
+    pub pricing_source: PricingSource,
+    /// Cost of requests billed at long-context (premium tier) rates
+    pub long_context_cost: f64,
 }
 
 #[derive(Debug, Serialize)]
@@ -48,11 +58,47 @@ pub struct ModelBreakdown {
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub cache_creation_tokens: u64,
+    /// Part of `cache_creation_tokens` written with a 1-hour cache lifetime
+    pub cache_creation_1h_tokens: u64,
     pub cache_read_tokens: u64,
     pub total_tokens: u64,
     pub cost_usd: f64,
 }
 
+/// Usage split between the main conversation and sidechains (Task subagents)
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct SidechainSplit {
+    pub main_tokens: u64,
+    pub main_cost_usd: f64,
+    pub sidechain_tokens: u64,
+    pub sidechain_cost_usd: f64,
+}
+
+impl SidechainSplit {
+    pub fn add(&mut self, is_sidechain: bool, tokens: u64, cost_usd: f64) {
+        if is_sidechain {
+            self.sidechain_tokens += tokens;
+            self.sidechain_cost_usd += cost_usd;
+        } else {
+            self.main_tokens += tokens;
+            self.main_cost_usd += cost_usd;
+        }
+    }
+
+    pub fn merge(&mut self, other: &SidechainSplit) {
+        self.main_tokens += other.main_tokens;
+        self.main_cost_usd += other.main_cost_usd;
+        self.sidechain_tokens += other.sidechain_tokens;
+        self.sidechain_cost_usd += other.sidechain_cost_usd;
+    }
+
+    /// Share of the cost spent in sidechains, from 0 to 1
+    pub fn sidechain_cost_share(&self) -> f64 {
+        let total = self.main_cost_usd + self.sidechain_cost_usd;
+        if total > 0.0 { self.sidechain_cost_usd / total } else { 0.0 }
+    }
+}
+
 #[derive(Debug, Default, Serialize)]
 pub struct DailyStats {
     pub date: String,
@@ -60,10 +106,16 @@ pub struct DailyStats {
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub cache_creation_tokens: u64,
+    /// Part of `cache_creation_tokens` written with a 1-hour cache lifetime
+    pub cache_creation_1h_tokens: u64,
     pub cache_read_tokens: u64,
     pub total_tokens: u64,
     pub cost_usd: f64,
     pub model_breakdowns: Vec<ModelBreakdown>,
+    pub sidechain: SidechainSplit,
+    pub pricing_source: PricingSource,
+    /// Cost of requests billed at long-context (premium tier) rates
+    pub long_context_cost_usd: f64,
 }
 
 
@@ -91,6 +143,15 @@ fn format_number_compact(n: u64) -> String {
     }
 }
 
+/// Footer line with the spend billed at long-context (premium tier) rates, if there was any
+pub fn format_long_context_note(long_context_cost: f64, total_cost: f64) -> Option<String> {
+    if long_context_cost <= 0.0 {
+        return None;
+    }
+    let share = if total_cost > 0.0 { long_context_cost / total_cost * 100.0 } else { 0.0 };
+    Some(format!("Long-context (premium tier) spend: ${:.2} ({:.1}% of cost)", long_context_cost, share))
+}
+
 fn format_models_list(models: &[String]) -> String {
     let mut result = String::new();
     for (i, model) in models.iter().enumerate() {
@@ -131,6 +192,7 @@ fn format_breakdown_table(daily_stats: &[DailyStats]) -> String {
     // Calculate totals
     let mut grand_total_tokens = 0u64;
     let mut grand_total_cost = 0.0;
+    let mut grand_long_context_cost = 0.0;
 
     for stats in daily_stats {
         output.push_str(&format!("\n{green}📅 {}{reset}\n", stats.date));
@@ -142,29 +204,30 @@ fn format_breakdown_table(daily_stats: &[DailyStats]) -> String {
 
         // Table header for this date
         output.push_str(&format!(
-            "{gray}┌─────────────{reset}{gray}┬──────────{reset}{gray}┬──────────{reset}{gray}┬──────────{reset}{gray}┬──────────{reset}{gray}┬──────────{reset}{gray}┬──────────┐{reset}\n"
+            "{gray}┌─────────────{reset}{gray}┬──────────{reset}{gray}┬──────────{reset}{gray}┬──────────{reset}{gray}┬──────────{reset}{gray}┬──────────{reset}{gray}┬──────────{reset}{gray}┬──────────┐{reset}\n"
         ));
         
         output.push_str(&format!(
-            "{gray}│{reset}{cyan} Model       {reset}{gray}│{reset}{cyan}    Input {reset}{gray}│{reset}{cyan}   Output {reset}{gray}│{reset}{cyan}    Cache {reset}{gray}│{reset}{cyan}     Read {reset}{gray}│{reset}{cyan}    Total {reset}{gray}│{reset}{cyan}     Cost {reset}{gray}│{reset}\n"
+            "{gray}│{reset}{cyan} Model       {reset}{gray}│{reset}{cyan}    Input {reset}{gray}│{reset}{cyan}   Output {reset}{gray}│{reset}{cyan} Cache 5m {reset}{gray}│{reset}{cyan} Cache 1h {reset}{gray}│{reset}{cyan}     Read {reset}{gray}│{reset}{cyan}    Total {reset}{gray}│{reset}{cyan}     Cost {reset}{gray}│{reset}\n"
         ));
         
         output.push_str(&format!(
-            "{gray}│{reset}{cyan}             {reset}{gray}│{reset}{cyan}          {reset}{gray}│{reset}{cyan}          {reset}{gray}│{reset}{cyan}   Create {reset}{gray}│{reset}{cyan}          {reset}{gray}│{reset}{cyan}   Tokens {reset}{gray}│{reset}{cyan}    (USD) {reset}{gray}│{reset}\n"
+            "{gray}│{reset}{cyan}             {reset}{gray}│{reset}{cyan}          {reset}{gray}│{reset}{cyan}          {reset}{gray}│{reset}{cyan}    Write {reset}{gray}│{reset}{cyan}    Write {reset}{gray}│{reset}{cyan}          {reset}{gray}│{reset}{cyan}   Tokens {reset}{gray}│{reset}{cyan}    (USD) {reset}{gray}│{reset}\n"
         ));
 
         // Data rows for each model
         for breakdown in &stats.model_breakdowns {
             output.push_str(&format!(
-                "{gray}├─────────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────┤{reset}\n"
+                "{gray}├─────────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────┤{reset}\n"
             ));
 
             output.push_str(&format!(
-                "{gray}│{reset} {:<11} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset}\n",
+                "{gray}│{reset} {:<11} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset}\n",
                 breakdown.model_name,
                 format_number_compact(breakdown.input_tokens),
                 format_number_compact(breakdown.output_tokens),
-                format_number_compact(breakdown.cache_creation_tokens),
+                format_number_compact(breakdown.cache_creation_tokens - breakdown.cache_creation_1h_tokens),
+                format_number_compact(breakdown.cache_creation_1h_tokens),
                 format_number_compact(breakdown.cache_read_tokens),
                 format_number_compact(breakdown.total_tokens),
                 format!("${:.2}", breakdown.cost_usd)
@@ -173,30 +236,35 @@ fn format_breakdown_table(daily_stats: &[DailyStats]) -> String {
 
         // Totals row for this date
         output.push_str(&format!(
-            "{gray}├─────────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────┤{reset}\n"
+            "{gray}├─────────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────{reset}{gray}┼──────────┤{reset}\n"
         ));
 
         output.push_str(&format!(
-            "{gray}│{reset} {green}Total{reset}       {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset}\n",
+            "{gray}│{reset} {green}Total{reset}       {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset} {:>8} {gray}│{reset}\n",
             format_number_compact(stats.input_tokens),
             format_number_compact(stats.output_tokens),
-            format_number_compact(stats.cache_creation_tokens),
+            format_number_compact(stats.cache_creation_tokens - stats.cache_creation_1h_tokens),
+            format_number_compact(stats.cache_creation_1h_tokens),
             format_number_compact(stats.cache_read_tokens),
             format_number_compact(stats.total_tokens),
-            format!("${:.2}", stats.cost_usd)
+            format!("${:.2}{}", stats.cost_usd, stats.pricing_source.marker())
         ));
 
         output.push_str(&format!(
-            "{gray}└─────────────{reset}{gray}┴──────────{reset}{gray}┴──────────{reset}{gray}┴──────────{reset}{gray}┴──────────{reset}{gray}┴──────────{reset}{gray}┴──────────┘{reset}\n"
+            "{gray}└─────────────{reset}{gray}┴──────────{reset}{gray}┴──────────{reset}{gray}┴──────────{reset}{gray}┴──────────{reset}{gray}┴──────────{reset}{gray}┴──────────{reset}{gray}┴──────────┘{reset}\n"
         ));
 
         grand_total_tokens += stats.total_tokens;
         grand_total_cost += stats.cost_usd;
+        grand_long_context_cost += stats.long_context_cost_usd;
     }
 
     // Grand totals
     output.push_str(&format!("\n{green}📊 Grand Total: {} tokens | ${:.2}{reset}\n", 
         format_number_compact(grand_total_tokens), grand_total_cost));
+    if let Some(note) = format_long_context_note(grand_long_context_cost, grand_total_cost) {
+        output.push_str(&format!("{}\n", note));
+    }
 
     output
 }
@@ -236,6 +304,7 @@ fn format_standard_table(daily_stats: &[DailyStats]) -> String {
     let mut total_cache_read = 0u64;
     let mut total_tokens = 0u64;
     let mut total_cost = 0.0;
+    let mut total_long_context_cost = 0.0;
 
     // Data rows
     for stats in daily_stats.iter() {
@@ -267,7 +336,7 @@ fn format_standard_table(daily_stats: &[DailyStats]) -> String {
             format_number_compact(stats.cache_creation_tokens),
             format_number_compact(stats.cache_read_tokens),
             format_number_compact(stats.total_tokens),
-            format!("${:.2}", stats.cost_usd),
+            format!("${:.2}{}", stats.cost_usd, stats.pricing_source.marker()),
             gray = "\x1b[90m", reset = "\x1b[39m"
         ));
 
@@ -294,6 +363,7 @@ fn format_standard_table(daily_stats: &[DailyStats]) -> String {
         total_cache_read += stats.cache_read_tokens;
         total_tokens += stats.total_tokens;
         total_cost += stats.cost_usd;
+        total_long_context_cost += stats.long_context_cost_usd;
     }
 
     // Totals row
@@ -319,6 +389,10 @@ fn format_standard_table(daily_stats: &[DailyStats]) -> String {
         gray = "\x1b[90m", reset = "\x1b[39m"
     ));
 
+    if let Some(note) = format_long_context_note(total_long_context_cost, total_cost) {
+        output.push_str(&format!("\n{}\n", note));
+    }
+
     output
 }
 
@@ -327,22 +401,20 @@ pub fn generate_json_output(daily_stats: &[DailyStats]) -> Result<JsonOutput> {
     let mut json_daily = Vec::new();
     
     for stats in daily_stats {
-        // Create model breakdowns from the models list
-        let mut model_breakdowns = Vec::new();
-        
-        // For now, we'll aggregate all tokens under the primary model
-        // This is a simplification - ideally we'd track per-model usage separately
-        if !stats.models.is_empty() {
-            let primary_model = &stats.models[0];
-            model_breakdowns.push(JsonModelBreakdown {
-                model_name: primary_model.clone(),
-                input_tokens: stats.input_tokens,
-                output_tokens: stats.output_tokens,
-                cache_creation_tokens: stats.cache_creation_tokens,
-                cache_read_tokens: stats.cache_read_tokens,
-                cost: stats.cost_usd,
-            });
-        }
+        let model_breakdowns = stats
+            .model_breakdowns
+            .iter()
+            .map(|breakdown| JsonModelBreakdown {
+                model_name: breakdown.model_name.clone(),
+                input_tokens: breakdown.input_tokens,
+                output_tokens: breakdown.output_tokens,
+                cache_creation_tokens: breakdown.cache_creation_tokens,
+                cache_creation_5m_tokens: breakdown.cache_creation_tokens - breakdown.cache_creation_1h_tokens,
+                cache_creation_1h_tokens: breakdown.cache_creation_1h_tokens,
+                cache_read_tokens: breakdown.cache_read_tokens,
+                cost: breakdown.cost_usd,
+            })
+            .collect();
         
         json_daily.push(JsonDailyEntry {
             date: stats.date.clone(),
@@ -354,6 +426,9 @@ pub fn generate_json_output(daily_stats: &[DailyStats]) -> Result<JsonOutput> {
             total_cost: stats.cost_usd,
             models_used: stats.models.clone(),
             model_breakdowns,
+            sidechain: stats.sidechain,
+            pricing_source: stats.pricing_source,
+            long_context_cost: stats.long_context_cost_usd,
         });
     }
     
@@ -377,4 +452,58 @@ mod tests {
         assert_eq!(format_number_compact(1234567), "1.2M");
         assert_eq!(format_number_compact(999), "999");
     }
+
+    #[test]
+    fn test_daily_json_sidechain_matches_other_reports() {
+        let stats = DailyStats {
+            date: "2025-06-01".to_string(),
+            sidechain: SidechainSplit {
+                main_tokens: 100,
+                main_cost_usd: 1.0,
+                sidechain_tokens: 300,
+                sidechain_cost_usd: 3.0,
+            },
+            ..Default::default()
+        };
+
+        let daily = serde_json::to_value(generate_json_output(&[stats]).unwrap()).unwrap();
+        let sidechain = &daily["daily"][0]["sidechain"];
+        assert_eq!(sidechain["main_cost_usd"], 1.0);
+        assert_eq!(sidechain["sidechain_tokens"], 300);
+        assert_eq!(sidechain.as_object().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_daily_json_breaks_down_each_model() {
+        let breakdown = |model_name: &str, cache_creation_tokens, cache_creation_1h_tokens, cost_usd| ModelBreakdown {
+            model_name: model_name.to_string(),
+            input_tokens: 10,
+            output_tokens: 20,
+            cache_creation_tokens,
+            cache_creation_1h_tokens,
+            cache_read_tokens: 0,
+            total_tokens: 30 + cache_creation_tokens,
+            cost_usd,
+        };
+        let stats = DailyStats {
+            date: "2025-06-01".to_string(),
+            models: vec!["opus-4".to_string(), "sonnet-4".to_string()],
+            cache_creation_tokens: 300,
+            cache_creation_1h_tokens: 100,
+            cost_usd: 3.0,
+            model_breakdowns: vec![
+                breakdown("claude-opus-4-20250514", 200, 100, 2.0),
+                breakdown("claude-sonnet-4-20250514", 100, 0, 1.0),
+            ],
+            ..Default::default()
+        };
+
+        let daily = serde_json::to_value(generate_json_output(&[stats]).unwrap()).unwrap();
+        let breakdowns = daily["daily"][0]["modelBreakdowns"].as_array().unwrap();
+        assert_eq!(breakdowns.len(), 2);
+        assert_eq!(breakdowns[0]["cacheCreation5mTokens"], 100);
+        assert_eq!(breakdowns[0]["cacheCreation1hTokens"], 100);
+        assert_eq!(breakdowns[1]["cacheCreation1hTokens"], 0);
+        assert_eq!(breakdowns[1]["cost"], 1.0);
+    }
 }
\ No newline at end of file
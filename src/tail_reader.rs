@@ -0,0 +1,357 @@
+//! # Tail Reader Module
+//!
+//! Incremental ingestion of growing JSONL session files for the monitor loop
+//!
+//! ## Key Components
+//! - [`IncrementalSessions`] - Sessions kept up to date by parsing only appended data
+//! - [`FileTail`] - Byte offset and identity of a file that has already been read
+//! - [`FileTail::read_new_lines`] - Read only complete lines appended since the last read
+
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, Metadata};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::data_sources::DataSourceOptions;
+use crate::entry_processor::{FileRecords, parse_lines, project_name_for_file, sessions_from_records};
+use crate::jsonl_parser::{SessionData, SessionEntry};
+
+/// Read position and identity of a tailed file
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileTail {
+    /// Byte offset just past the last complete line consumed
+    pub offset: u64,
+    /// Inode (or 0 where unavailable) the offset refers to
+    pub inode: u64,
+    /// File size observed at the last read
+    pub size: u64,
+    /// Modification time observed at the last read
+    pub modified: Option<SystemTime>,
+}
+
+impl FileTail {
+    /// Whether the file was replaced, truncated or rewritten in place since the last read
+    ///
+    /// A rewrite that keeps the size is only visible through the modification time.
+    fn is_stale(&self, metadata: &Metadata) -> bool {
+        file_inode(metadata) != self.inode
+            || metadata.len() < self.offset
+            || (metadata.len() == self.size && metadata.modified().ok() != self.modified)
+    }
+
+    /// Read complete lines appended since the last read
+    ///
+    /// A trailing line without a newline is normally still being written and is
+    /// left for the next read. It is only consumed when it already parses as a
+    /// complete entry, so files that simply lack a final newline are not stuck.
+    pub fn read_new_lines(&mut self, path: &Path) -> Result<Vec<String>> {
+        let metadata = std::fs::metadata(path).context("Failed to stat JSONL file")?;
+        self.inode = file_inode(&metadata);
+        self.size = metadata.len();
+        self.modified = metadata.modified().ok();
+
+        if self.size <= self.offset {
+            return Ok(Vec::new());
+        }
+
+        let mut file = File::open(path).context("Failed to open JSONL file")?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buffer = Vec::with_capacity((self.size - self.offset) as usize);
+        file.take(self.size - self.offset).read_to_end(&mut buffer)?;
+
+        let mut lines = Vec::new();
+        let mut consumed = 0usize;
+
+        while let Some(newline) = buffer[consumed..].iter().position(|&b| b == b'\n') {
+            let line = &buffer[consumed..consumed + newline];
+            lines.push(String::from_utf8_lossy(line).into_owned());
+            consumed += newline + 1;
+        }
+
+        let remainder = &buffer[consumed..];
+        if !remainder.is_empty()
+            && let Ok(text) = std::str::from_utf8(remainder)
+            && serde_json::from_str::<SessionEntry>(text).is_ok()
+        {
+            lines.push(text.to_string());
+            consumed = buffer.len();
+        }
+
+        self.offset += consumed as u64;
+        Ok(lines)
+    }
+}
+
+#[derive(Debug, Default)]
+struct TrackedFile {
+    tail: FileTail,
+    /// Records of every line read so far
+    records: FileRecords,
+    /// Lines read so far, so issues keep the file's line numbers
+    lines_read: usize,
+}
+
+/// Sessions for a set of files, re-parsing only newly appended lines
+///
+/// Each file's records come from the same parser as the reports, and sessions
+/// are built from them with the same deduplication and grouping as the
+/// `session` report, so both show the same totals for the same files.
+#[derive(Debug, Default)]
+pub struct IncrementalSessions {
+    sources: DataSourceOptions,
+    files: HashMap<PathBuf, TrackedFile>,
+    /// Files in report order, which decides the copy of a repeated entry that counts
+    order: Vec<PathBuf>,
+    sessions: Vec<SessionData>,
+    /// Whether records changed since the sessions were built
+    dirty: bool,
+}
+
+impl IncrementalSessions {
+    pub fn new(sources: &DataSourceOptions) -> Self {
+        Self {
+            sources: sources.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Bring the sessions up to date with the current set of session files
+    ///
+    /// New files are read from the start, known files only from their last
+    /// offset, and replaced or truncated files are re-read from scratch. Files no
+    /// longer present are dropped.
+    pub fn refresh(&mut self, session_files: &[PathBuf]) -> Result<()> {
+        let current: HashSet<&PathBuf> = session_files.iter().collect();
+        let before = self.files.len();
+        self.files.retain(|path, _| current.contains(path));
+        self.dirty |= self.files.len() != before || self.order != session_files;
+        self.order = session_files.to_vec();
+        self.update(session_files)
+    }
+
+    /// Bring only the given files up to date, dropping those that no longer exist
+    pub fn update(&mut self, changed_files: &[PathBuf]) -> Result<()> {
+        for path in changed_files {
+            self.read_file(path)?;
+        }
+
+        if self.dirty {
+            let files = self
+                .order
+                .iter()
+                .filter_map(|path| self.files.get(path).map(|tracked| (path.as_path(), &tracked.records)));
+            self.sessions = sessions_from_records(files, &self.sources)?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Parse whatever is new in one file, starting over if it was replaced
+    fn read_file(&mut self, path: &Path) -> Result<()> {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            self.dirty |= self.files.remove(path).is_some();
+            self.order.retain(|known| known != path);
+            return Ok(());
+        };
+
+        if !self.order.iter().any(|known| known == path) {
+            self.order.push(path.to_path_buf());
+        }
+        if self.files.get(path).is_some_and(|tracked| tracked.tail.is_stale(&metadata)) {
+            self.files.remove(path);
+            self.dirty = true;
+        }
+        let tracked = self.files.entry(path.to_path_buf()).or_default();
+        if metadata.len() == tracked.tail.size && metadata.modified().ok() == tracked.tail.modified {
+            return Ok(());
+        }
+
+        match tracked.tail.read_new_lines(path) {
+            Ok(lines) if !lines.is_empty() => {
+                let first_line = tracked.lines_read + 1;
+                tracked.lines_read += lines.len();
+                let project = project_name_for_file(path);
+                parse_lines(&mut tracked.records, lines.into_iter().map(Ok), first_line, &project, None)?;
+                self.dirty = true;
+            }
+            Ok(_) => {}
+            Err(e) => log::debug!("Failed to tail {}: {}", path.display(), e),
+        }
+        Ok(())
+    }
+
+    /// All sessions built so far
+    pub fn sessions(&self) -> impl Iterator<Item = &SessionData> {
+        self.sessions.iter()
+    }
+}
+
+#[cfg(unix)]
+fn file_inode(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn file_inode(_metadata: &Metadata) -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::io::Write;
+
+    fn usage_line(id: u32, minute: u32) -> String {
+        format!(
+            r#"{{"sessionId":"s1","type":"assistant","timestamp":"2025-06-01T10:{:02}:00Z","requestId":"r{}","message":{{"id":"m{}","model":"claude-sonnet-4-20250514","usage":{{"input_tokens":10,"output_tokens":20}}}}}}"#,
+            minute, id, id
+        )
+    }
+
+
+    #[test]
+    fn test_partial_line_is_deferred() {
+        let dir = TempDir::new("tail-partial");
+        let path = dir.join("session.jsonl");
+        let full = usage_line(1, 0);
+        let second = usage_line(2, 1);
+        let mut file = File::create(&path).unwrap();
+        write!(file, "{}\n{}", full, &second[..20]).unwrap();
+        file.flush().unwrap();
+
+        let mut tail = FileTail::default();
+        assert_eq!(tail.read_new_lines(&path).unwrap(), vec![full.clone()]);
+        assert_eq!(tail.offset, full.len() as u64 + 1);
+
+        writeln!(file, "{}", &second[20..]).unwrap();
+        file.flush().unwrap();
+        assert_eq!(tail.read_new_lines(&path).unwrap(), vec![second]);
+        assert!(tail.read_new_lines(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_incremental_sessions_follow_appends_and_truncation() {
+        let dir = TempDir::new("tail-sessions");
+        let path = dir.join("session.jsonl");
+        std::fs::write(&path, format!("{}\n", usage_line(1, 0))).unwrap();
+
+        let mut sessions = IncrementalSessions::new(&DataSourceOptions::default());
+        sessions.refresh(std::slice::from_ref(&path)).unwrap();
+        let total = |sessions: &IncrementalSessions| {
+            sessions.sessions().map(|s| s.model_usage.values().map(|u| u.total_output).sum::<u64>()).sum::<u64>()
+        };
+        assert_eq!(total(&sessions), 20);
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "{}", usage_line(2, 5)).unwrap();
+        sessions.refresh(std::slice::from_ref(&path)).unwrap();
+        assert_eq!(total(&sessions), 40);
+
+        // Rewriting the file with less data starts over
+        std::fs::write(&path, format!("{}\n", usage_line(3, 9))).unwrap();
+        sessions.refresh(std::slice::from_ref(&path)).unwrap();
+        assert_eq!(total(&sessions), 20);
+
+        // The same request copied into another file is counted once
+        let copy = dir.join("copy.jsonl");
+        std::fs::write(&copy, format!("{}\n{}\n", usage_line(3, 9), usage_line(4, 12))).unwrap();
+        sessions.refresh(&[path.clone(), copy.clone()]).unwrap();
+        assert_eq!(total(&sessions), 40);
+
+        sessions.refresh(&[]).unwrap();
+        assert_eq!(sessions.sessions().count(), 0);
+    }
+    #[test]
+    fn test_same_size_rewrite_is_reread() {
+        let dir = TempDir::new("tail-rewrite");
+        let path = dir.join("session.jsonl");
+        let line = usage_line(1, 0);
+        std::fs::write(&path, format!("{}\n", line)).unwrap();
+
+        let mut sessions = IncrementalSessions::new(&DataSourceOptions::default());
+        sessions.refresh(std::slice::from_ref(&path)).unwrap();
+        let total = |sessions: &IncrementalSessions| {
+            sessions.sessions().map(|s| s.model_usage.values().map(|u| u.total_output).sum::<u64>()).sum::<u64>()
+        };
+        assert_eq!(total(&sessions), 20);
+
+        // Same length, different usage, written in place with a later modification time
+        let rewritten = usage_line(2, 0).replace(r#""output_tokens":20"#, r#""output_tokens":30"#);
+        assert_eq!(rewritten.len(), line.len());
+        let mut file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        writeln!(file, "{}", rewritten).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(5)).unwrap();
+        drop(file);
+
+        sessions.refresh(std::slice::from_ref(&path)).unwrap();
+        assert_eq!(total(&sessions), 30);
+    }
+
+    #[test]
+    fn test_removed_original_releases_entries_to_resumed_session() {
+        let dir = TempDir::new("tail-resumed");
+        let original = dir.join("original.jsonl");
+        let resumed = dir.join("resumed.jsonl");
+        std::fs::write(&original, format!("{}\n{}\n", usage_line(1, 0), usage_line(2, 1))).unwrap();
+        // The resumed session starts with copies of the original's entries
+        std::fs::write(&resumed, format!("{}\n{}\n{}\n", usage_line(1, 0), usage_line(2, 1), usage_line(3, 2))).unwrap();
+
+        let mut sessions = IncrementalSessions::new(&DataSourceOptions::default());
+        sessions.refresh(&[original.clone(), resumed.clone()]).unwrap();
+        let total = |sessions: &IncrementalSessions| {
+            sessions.sessions().map(|s| s.model_usage.values().map(|u| u.total_output).sum::<u64>()).sum::<u64>()
+        };
+        assert_eq!(total(&sessions), 60);
+
+        std::fs::remove_file(&original).unwrap();
+        sessions.refresh(std::slice::from_ref(&resumed)).unwrap();
+        assert_eq!(total(&sessions), 60);
+
+        // Matches the full reports over the remaining file
+        let files = std::slice::from_ref(&resumed);
+        let sources = crate::data_sources::DataSourceOptions::default();
+        let loaded: u64 = crate::entry_processor::load_sessions(files, &sources)
+            .unwrap()
+            .iter()
+            .map(|s| s.model_usage.values().map(|u| u.total_output).sum::<u64>())
+            .sum();
+        let daily: u64 = crate::entry_processor::process_all_entries(files, &sources).unwrap().iter().map(|day| day.output_tokens).sum();
+        assert_eq!(loaded, 60);
+        assert_eq!(daily, 60);
+    }
+
+    #[test]
+    fn test_sessions_match_session_report() {
+        let dir = TempDir::new("tail-report");
+        let first = dir.join("first.jsonl");
+        let second = dir.join("second.jsonl");
+        // One session continued in a second file, with a sidechain entry and a malformed line
+        let sidechain = usage_line(3, 2).replace(r#""type":"assistant""#, r#""type":"assistant","isSidechain":true"#);
+        std::fs::write(&first, format!("{}\n{{\"sessionId\":\n", usage_line(1, 0))).unwrap();
+        std::fs::write(&second, format!("{}\n{}\n{}\n", usage_line(1, 0), usage_line(2, 1), sidechain)).unwrap();
+        let files = [first.clone(), second.clone()];
+
+        let sources = DataSourceOptions::default();
+        let mut sessions = IncrementalSessions::new(&sources);
+        sessions.refresh(&files).unwrap();
+        let monitored: Vec<&SessionData> = sessions.sessions().collect();
+        let reported = crate::entry_processor::load_sessions(&files, &sources).unwrap();
+
+        assert_eq!(monitored.len(), 1);
+        assert_eq!(reported.len(), 1);
+        assert_eq!(monitored[0].session_id, reported[0].session_id);
+        assert_eq!(monitored[0].sidechain.sidechain_tokens, 30);
+        assert_eq!(monitored[0].sidechain.main_tokens, reported[0].sidechain.main_tokens);
+        assert_eq!(monitored[0].total_weighted_tokens, reported[0].total_weighted_tokens);
+
+        let strict = DataSourceOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(IncrementalSessions::new(&strict).refresh(&files).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+//! # Test Support
+//!
+//! Fixtures shared by the unit tests
+//!
+//! ## Key Components
+//! - [`TempDir`] - Scratch directory removed when the test ends
+
+use std::path::{Path, PathBuf};
+
+/// Scratch directory under the system temp dir, removed on drop
+///
+/// The directory name carries the process id so concurrent test runs don't
+/// collide; tests within one run must pick distinct names.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create an empty directory, clearing anything left by an aborted run
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("ccusage-rs-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,262 @@
+//! # Tool Usage Module
+//!
+//! Attributes assistant turns to the tools they invoked, from the `tool_use` and
+//! `tool_result` blocks of message content, so expensive tools and MCP servers
+//! stand out
+//!
+//! ## Key Components
+//! - [`load_tool_turns`] - Read session files into assistant turns with their tool calls
+//! - [`aggregate_tool_usage`] - Rank tools by calls, attributed output tokens and cost
+//! - [`ToolTurn`] - One deduplicated assistant turn and the tools it called
+//! - [`ToolStats`] - Calls, failures and attributed usage of one tool
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::data_sources::DataSourceOptions;
+use crate::entry_processor::for_each_record;
+use crate::pricing::{check_unknown_models, entry_cost};
+
+/// One `tool_use` block of an assistant turn
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    pub name: String,
+    /// Whether the matching `tool_result` was flagged `is_error`
+    pub failed: bool,
+}
+
+/// An assistant turn: usage is counted once, even though Claude Code writes each
+/// content block of a response as its own line
+#[derive(Debug, Clone)]
+pub struct ToolTurn {
+    pub timestamp: DateTime<Utc>,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    pub tools: Vec<ToolCall>,
+}
+
+/// Usage attributed to one tool, or to one MCP server
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ToolStats {
+    pub tool: String,
+    pub calls: usize,
+    /// Calls whose result was an error
+    pub errors: usize,
+    /// Assistant turns that called the tool at least once
+    pub turns: usize,
+    /// Output tokens of those turns, split evenly over the calls they made
+    pub output_tokens: u64,
+    /// Cost of those turns, split the same way
+    pub cost_usd: f64,
+}
+
+/// Turn collected while reading, with tool calls still keyed by `tool_use` id
+#[derive(Debug)]
+struct PendingTurn {
+    timestamp: DateTime<Utc>,
+    has_usage: bool,
+    output_tokens: u64,
+    cost_usd: f64,
+    tool_use_ids: Vec<(String, String)>,
+}
+
+/// Read every assistant turn and the tools it called
+///
+/// Turns come from the same records as the usage reports. Lines sharing a message
+/// id and request id belong to one turn and are merged; turns and tool calls
+/// repeated in a resumed session file are counted once.
+pub fn load_tool_turns(session_files: &[PathBuf], sources: &DataSourceOptions) -> Result<Vec<ToolTurn>> {
+    let mut turns: Vec<PendingTurn> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut seen_tool_uses: HashSet<String> = HashSet::new();
+    let mut failed_tool_uses: HashSet<String> = HashSet::new();
+
+    for_each_record(session_files, sources, |_| {}, |record, counted| {
+        failed_tool_uses.extend(record.failed_tool_uses.iter().cloned());
+
+        if !record.billable && record.tool_uses.is_empty() {
+            return;
+        }
+        let Some(key) = record.hash.clone().or_else(|| (!record.uuid.is_empty()).then(|| record.uuid.clone())) else {
+            return;
+        };
+
+        let position = *positions.entry(key).or_insert_with(|| {
+            turns.push(PendingTurn {
+                timestamp: record.timestamp,
+                has_usage: false,
+                output_tokens: 0,
+                cost_usd: 0.0,
+                tool_use_ids: Vec::new(),
+            });
+            turns.len() - 1
+        });
+        let turn = &mut turns[position];
+
+        if counted {
+            turn.has_usage = true;
+            turn.output_tokens = record.usage.output_tokens;
+            turn.cost_usd = entry_cost(&record.usage, &record.model, record.cost_usd, record.timestamp).0;
+        }
+
+        for tool_use in &record.tool_uses {
+            if seen_tool_uses.insert(tool_use.id.clone()) {
+                turn.tool_use_ids.push((tool_use.id.clone(), tool_use.name.clone()));
+            }
+        }
+    })?;
+
+    check_unknown_models()?;
+    Ok(turns
+        .into_iter()
+        .filter(|turn| turn.has_usage || !turn.tool_use_ids.is_empty())
+        .map(|turn| ToolTurn {
+            timestamp: turn.timestamp,
+            output_tokens: turn.output_tokens,
+            cost_usd: turn.cost_usd,
+            tools: turn
+                .tool_use_ids
+                .into_iter()
+                .map(|(id, name)| ToolCall {
+                    failed: failed_tool_uses.contains(&id),
+                    name,
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+/// Attribute each turn's output tokens and cost to the tools it called, evenly per call
+///
+/// With `by_server`, MCP tools (`mcp__<server>__<tool>`) are combined per server.
+/// Turns without tool calls are not attributed to any tool. Results are ordered
+/// by cost, highest first.
+pub fn aggregate_tool_usage(turns: &[ToolTurn], by_server: bool) -> Vec<ToolStats> {
+    let mut stats: Vec<ToolStats> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut output_shares: Vec<f64> = Vec::new();
+
+    for turn in turns.iter().filter(|turn| !turn.tools.is_empty()) {
+        let share = 1.0 / turn.tools.len() as f64;
+        let mut counted_in_turn: HashSet<usize> = HashSet::new();
+
+        for call in &turn.tools {
+            let tool = if by_server { server_name(&call.name) } else { call.name.clone() };
+            let position = *positions.entry(tool.clone()).or_insert_with(|| {
+                stats.push(ToolStats {
+                    tool,
+                    ..Default::default()
+                });
+                output_shares.push(0.0);
+                stats.len() - 1
+            });
+
+            let stat = &mut stats[position];
+            stat.calls += 1;
+            if call.failed {
+                stat.errors += 1;
+            }
+            if counted_in_turn.insert(position) {
+                stat.turns += 1;
+            }
+            stat.cost_usd += turn.cost_usd * share;
+            output_shares[position] += turn.output_tokens as f64 * share;
+        }
+    }
+
+    for (stat, output_tokens) in stats.iter_mut().zip(output_shares) {
+        stat.output_tokens = output_tokens.round() as u64;
+    }
+    stats.sort_by(|a, b| {
+        b.cost_usd
+            .partial_cmp(&a.cost_usd)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.tool.cmp(&b.tool))
+    });
+    stats
+}
+
+/// `mcp__<server>` for MCP tools, the tool name itself for built-in tools
+fn server_name(tool: &str) -> String {
+    match tool.strip_prefix("mcp__").and_then(|rest| rest.split("__").next()) {
+        Some(server) if !server.is_empty() => format!("mcp__{}", server),
+        _ => tool.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn turn(output_tokens: u64, cost_usd: f64, tools: &[(&str, bool)]) -> ToolTurn {
+        ToolTurn {
+            timestamp: Utc::now(),
+            output_tokens,
+            cost_usd,
+            tools: tools
+                .iter()
+                .map(|&(name, failed)| ToolCall {
+                    name: name.to_string(),
+                    failed,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_turn_usage_is_split_over_tool_calls() {
+        let turns = vec![
+            turn(300, 3.0, &[("Read", false), ("Read", false), ("mcp__github__create_issue", true)]),
+            turn(100, 2.0, &[("mcp__github__list_issues", false)]),
+            turn(500, 9.0, &[]),
+        ];
+
+        let by_tool = aggregate_tool_usage(&turns, false);
+        assert_eq!(by_tool.len(), 3);
+        assert_eq!(by_tool[0].tool, "Read");
+        assert_eq!((by_tool[0].calls, by_tool[0].turns, by_tool[0].output_tokens), (2, 1, 200));
+        assert_eq!(by_tool[0].cost_usd, 2.0);
+
+        let by_server = aggregate_tool_usage(&turns, true);
+        assert_eq!(by_server[0].tool, "mcp__github");
+        assert_eq!((by_server[0].calls, by_server[0].errors, by_server[0].turns), (2, 1, 2));
+        assert_eq!(by_server[0].output_tokens, 200);
+        assert_eq!(by_server[0].cost_usd, 3.0);
+
+        // Turns without tool calls are not attributed
+        let attributed: f64 = by_tool.iter().map(|stat| stat.cost_usd).sum();
+        assert_eq!(attributed, 5.0);
+    }
+
+    #[test]
+    fn test_tool_turns_use_shared_records_across_files() {
+        let dir = TempDir::new("tools-shared");
+        let project = dir.join("-home-me-repo");
+        std::fs::create_dir_all(&project).unwrap();
+        let first = project.join("first.jsonl");
+        let resumed = project.join("resumed.jsonl");
+
+        let call = r#"{"sessionId":"s1","uuid":"a","timestamp":"2025-06-01T10:00:00Z","requestId":"r1","message":{"id":"m1","role":"assistant","model":"claude-sonnet-4-20250514","content":[{"type":"tool_use","id":"t1","name":"Bash"}],"usage":{"input_tokens":10,"output_tokens":40},"costUSD":1.0}}"#;
+        let failure = r#"{"sessionId":"s1","uuid":"b","parentUuid":"a","timestamp":"2025-06-01T10:00:05Z","type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","is_error":true}]}}"#;
+        std::fs::write(&first, [call, failure].join("\n")).unwrap();
+        // The resumed file repeats the turn and adds a malformed line
+        std::fs::write(&resumed, [call, r#"{"sessionId":"s1","uu"#].join("\n")).unwrap();
+        let files = [first, resumed];
+
+        let turns = load_tool_turns(&files, &DataSourceOptions::default()).unwrap();
+        assert_eq!(turns.len(), 1);
+        assert_eq!((turns[0].output_tokens, turns[0].cost_usd), (40, 1.0));
+        assert_eq!(turns[0].tools.len(), 1);
+        assert!(turns[0].tools[0].failed);
+
+        let strict = DataSourceOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(load_tool_turns(&files, &strict).is_err());
+    }
+}
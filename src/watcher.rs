@@ -0,0 +1,279 @@
+//! # Watcher Module
+//!
+//! Filesystem notifications for the monitor, so appended lines and new sessions
+//! show up without rescanning every project directory
+//!
+//! ## Key Components
+//! - [`SessionWatcher`] - Recursive watcher over the Claude data roots
+//! - [`WatchBatch`] - Paths reported by one burst of filesystem events
+//! - [`SessionFileSet`] - Known project directories and session files, updated from events
+
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration as StdDuration;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+use tokio::time::Instant;
+
+use crate::data_sources::DataSourceOptions;
+
+/// How long to wait for related events after the first one before reporting a batch
+const EVENT_COALESCE_WINDOW: StdDuration = StdDuration::from_millis(20);
+
+/// Paths touched by a burst of filesystem events
+#[derive(Debug, Default)]
+pub struct WatchBatch {
+    pub paths: Vec<PathBuf>,
+    /// Events were lost (e.g. the kernel queue overflowed); a full rescan is needed
+    pub rescan: bool,
+}
+
+/// Recursive filesystem watcher over the Claude project roots
+pub struct SessionWatcher {
+    _watcher: RecommendedWatcher,
+    batcher: EventBatcher,
+}
+
+impl SessionWatcher {
+    /// Watch every existing root recursively, failing if none can be watched
+    pub fn start(roots: &[PathBuf]) -> Result<Self> {
+        let (sender, events) = unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })
+        .context("Failed to create filesystem watcher")?;
+
+        let mut watched = 0;
+        for root in roots.iter().filter(|root| root.is_dir()) {
+            match watcher.watch(root, RecursiveMode::Recursive) {
+                Ok(()) => watched += 1,
+                Err(e) => log::debug!("Failed to watch {}: {}", root.display(), e),
+            }
+        }
+
+        if watched == 0 {
+            anyhow::bail!("No Claude data root could be watched");
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            batcher: EventBatcher::new(events),
+        })
+    }
+
+    /// Wait for the next burst of relevant events, or `None` if the watcher stopped
+    ///
+    /// Cancel-safe: events received by a call that is dropped (e.g. when another
+    /// `select!` branch wins) are kept and returned by the next call.
+    pub async fn next_batch(&mut self) -> Option<WatchBatch> {
+        self.batcher.next_batch().await
+    }
+}
+
+/// Coalesces raw events into batches, keeping a started batch across cancelled calls
+struct EventBatcher {
+    events: UnboundedReceiver<notify::Result<Event>>,
+    /// Batch opened by the first event of a burst, and when it is due
+    pending: Option<(WatchBatch, Instant)>,
+}
+
+impl EventBatcher {
+    fn new(events: UnboundedReceiver<notify::Result<Event>>) -> Self {
+        Self { events, pending: None }
+    }
+
+    async fn next_batch(&mut self) -> Option<WatchBatch> {
+        if self.pending.is_none() {
+            let first = self.events.recv().await?;
+            let mut batch = WatchBatch::default();
+            add_event(&mut batch, first);
+            self.pending = Some((batch, Instant::now() + EVENT_COALESCE_WINDOW));
+        }
+
+        // Appends usually arrive as several events in quick succession
+        let due = self.pending.as_ref().map(|(_, due)| *due)?;
+        tokio::time::sleep_until(due).await;
+
+        let (mut batch, _) = self.pending.take()?;
+        while let Ok(event) = self.events.try_recv() {
+            add_event(&mut batch, event);
+        }
+
+        let mut seen = HashSet::new();
+        batch.paths.retain(|path| seen.insert(path.clone()));
+        Some(batch)
+    }
+}
+
+fn add_event(batch: &mut WatchBatch, event: notify::Result<Event>) {
+    match event {
+        Ok(event) => {
+            if event.need_rescan() {
+                batch.rescan = true;
+            }
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+                batch.paths.extend(event.paths);
+            }
+        }
+        Err(e) => {
+            log::debug!("Filesystem watcher error: {}", e);
+            batch.rescan = true;
+        }
+    }
+}
+
+/// Project directories and session files currently being monitored
+#[derive(Debug, Default)]
+pub struct SessionFileSet {
+    roots: Vec<PathBuf>,
+    project_dirs: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+}
+
+impl SessionFileSet {
+    /// Scan every root for project directories and live (uncompressed) session files
+    pub fn discover(sources: &DataSourceOptions) -> Self {
+        let project_dirs = sources.project_dirs();
+        let mut files = sources.session_files(&project_dirs).unwrap_or_default();
+        // Compressed and archived logs are history that never grows
+        files.retain(|file| is_session_file(file));
+
+        Self {
+            roots: sources.roots(),
+            project_dirs,
+            files,
+        }
+    }
+
+    pub fn project_dirs(&self) -> &[PathBuf] {
+        &self.project_dirs
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Update the set from changed paths, returning the session files to re-read
+    ///
+    /// Only the touched paths are inspected; the project list is re-read (one
+    /// directory listing per root) when a new project directory appears.
+    pub fn apply_changes(&mut self, sources: &DataSourceOptions, paths: &[PathBuf]) -> Vec<PathBuf> {
+        let touches_projects = paths.iter().any(|path| {
+            let parent = path.parent();
+            if is_session_file(path) {
+                parent.is_some_and(|dir| !self.project_dirs.iter().any(|known| known == dir))
+            } else {
+                parent.is_some_and(|dir| self.roots.iter().any(|root| root == dir))
+            }
+        });
+
+        let mut changed = Vec::new();
+
+        if touches_projects {
+            let project_dirs = sources.project_dirs();
+            let added: Vec<PathBuf> = project_dirs
+                .iter()
+                .filter(|dir| !self.project_dirs.contains(dir))
+                .cloned()
+                .collect();
+
+            // Files created before the new directory was being watched produce no events
+            for file in sources.session_files(&added).unwrap_or_default().into_iter().filter(|file| is_session_file(file)) {
+                if !self.files.contains(&file) {
+                    self.files.push(file.clone());
+                }
+                changed.push(file);
+            }
+
+            self.files
+                .retain(|file| file.parent().is_some_and(|dir| project_dirs.iter().any(|known| known == dir)));
+            self.project_dirs = project_dirs;
+        }
+
+        for path in paths.iter().filter(|path| is_session_file(path)) {
+            let in_project = path
+                .parent()
+                .is_some_and(|dir| self.project_dirs.iter().any(|known| known == dir));
+            if !in_project {
+                continue;
+            }
+
+            if path.exists() {
+                if !self.files.contains(path) {
+                    self.files.push(path.clone());
+                }
+            } else {
+                self.files.retain(|file| file != path);
+            }
+            if !changed.contains(path) {
+                changed.push(path.clone());
+            }
+        }
+
+        changed
+    }
+}
+
+fn is_session_file(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("jsonl")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_new_projects_and_sessions_are_picked_up_from_events() {
+        let home = TempDir::new("watch");
+        let root = home.join("projects");
+        let alpha = root.join("-home-me-alpha");
+        std::fs::create_dir_all(&alpha).unwrap();
+        std::fs::write(alpha.join("s1.jsonl"), "").unwrap();
+
+        let sources = DataSourceOptions {
+            claude_dir: Some(root.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        let mut set = SessionFileSet::discover(&sources);
+        assert_eq!(set.files().len(), 1);
+
+        // A new session in a known project
+        let s2 = alpha.join("s2.jsonl");
+        std::fs::write(&s2, "").unwrap();
+        assert_eq!(set.apply_changes(&sources, std::slice::from_ref(&s2)), vec![s2.clone()]);
+
+        // A new project whose first file was written before any event for it arrived
+        let beta = root.join("-home-me-beta");
+        std::fs::create_dir_all(&beta).unwrap();
+        std::fs::write(beta.join("s3.jsonl"), "").unwrap();
+        let changed = set.apply_changes(&sources, std::slice::from_ref(&beta));
+        assert_eq!(changed, vec![beta.join("s3.jsonl")]);
+        assert_eq!(set.project_dirs().len(), 2);
+        assert_eq!(set.files().len(), 3);
+
+        // Removed files are dropped
+        std::fs::remove_file(&s2).unwrap();
+        set.apply_changes(&sources, std::slice::from_ref(&s2));
+        assert_eq!(set.files().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_batch_is_resumed() {
+        let (sender, events) = unbounded_channel();
+        let mut batcher = EventBatcher::new(events);
+        let modified = |path: &str| Ok(Event::new(EventKind::Modify(notify::event::ModifyKind::Any)).add_path(PathBuf::from(path)));
+
+        // The first event is received, then the call is dropped while coalescing
+        sender.send(modified("a.jsonl")).unwrap();
+        tokio::select! {
+            _ = batcher.next_batch() => panic!("batch completed before the coalesce window"),
+            _ = tokio::time::sleep(StdDuration::from_millis(5)) => {}
+        }
+
+        sender.send(modified("b.jsonl")).unwrap();
+        let batch = batcher.next_batch().await.unwrap();
+        assert_eq!(batch.paths, vec![PathBuf::from("a.jsonl"), PathBuf::from("b.jsonl")]);
+    }
+}